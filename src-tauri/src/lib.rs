@@ -1,20 +1,35 @@
-use chrono::{Datelike, Duration, Local, NaiveDate, NaiveTime, TimeZone, Utc};
-use rusqlite::{params, Connection};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chrono::{
+  DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, OpenFlags};
 use serde::de::{self, Deserializer};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use tauri::{AppHandle, Manager, State};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tauri::{AppHandle, Emitter, Manager, State};
 use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_shell::ShellExt;
 use tokio::sync::Notify;
 use tokio::time::{sleep, Duration as TokioDuration};
 use uuid::Uuid;
 
+/// Commands share one connection, locked per call. The scheduler and the
+/// snapshot cache writer open their own so they never wait on UI commands.
 #[derive(Clone)]
 struct DbState {
   db_path: PathBuf,
+  conn: Arc<Mutex<Connection>>,
+  cache_path: PathBuf,
+  cache_dirty: Arc<Notify>,
 }
 
 #[derive(Clone)]
@@ -27,10 +42,39 @@ struct ReminderCandidate {
   task_id: String,
   task_title: String,
   task_detail: Option<String>,
+  list_id: Option<String>,
   list_name: Option<String>,
   due_date: String,
   time: String,
   remind_at_ms: i64,
+  snoozed_from: Option<i64>,
+}
+
+/// Emitted with the permission label when a reminder could not be shown because the
+/// OS has not granted notification permission.
+const NOTIFICATION_BLOCKED_EVENT: &str = "notification-blocked";
+
+/// One reminder a notification's action buttons act on.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReminderActionTarget {
+  task_id: String,
+  remind_at: i64,
+}
+
+/// Name of the event emitted to the webview each time the scheduler fires a reminder.
+const REMINDER_FIRED_EVENT: &str = "reminder-fired";
+
+/// Payload of `REMINDER_FIRED_EVENT`: `{ "taskId": string, "remindAt": number,
+/// "actionRemindAt": number }`, where `remindAt` is the epoch-millisecond instant
+/// that fired and `actionRemindAt` is what `handle_reminder_action` expects, the
+/// original due time for a snoozed reminder, as in the notification extras.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReminderFiredEvent {
+  task_id: String,
+  remind_at: i64,
+  action_remind_at: i64,
 }
 
 #[derive(Debug, Serialize)]
@@ -45,8 +89,113 @@ struct DebugNextReminder {
   delay_ms: i64,
 }
 
-const REMINDER_GRACE_MS: i64 = 10 * 60 * 1000;
-const FIRED_REMINDER_RETENTION_MS: i64 = 30 * 24 * 60 * 60 * 1000;
+/// One task's reminder state as the scheduler sees it, for diagnosing reminders
+/// that did not fire.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DebugTaskReminders {
+  task_id: String,
+  now: i64,
+  /// `None` when the task has no reminder, is completed, or its date/time
+  /// (or dependency) is not set yet.
+  remind_at: Option<i64>,
+  fired: bool,
+  /// Whether `remind_at` has passed but is still recent enough to be fired late.
+  in_grace_window: bool,
+  next_repeat_date: Option<String>,
+}
+
+/// A deleted task (`kind` "task") or list (`kind` "list") as recorded in `undo_log`.
+/// For a list, `task_ids` are the tasks it held and `moved_to` is where they went.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeletedEntity {
+  kind: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  task: Option<TaskItem>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  list: Option<ListItem>,
+  #[serde(default)]
+  task_ids: Vec<String>,
+  #[serde(default)]
+  moved_to: Option<String>,
+}
+
+/// The toggled task, plus the next occurrence when completing it spawned one.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ToggleTaskResult {
+  task: TaskItem,
+  spawned: Option<TaskItem>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskChange {
+  id: i64,
+  task_id: String,
+  change_type: String,
+  field: Option<String>,
+  old_value: Option<String>,
+  new_value: Option<String>,
+  changed_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SnoozeEntry {
+  task_id: String,
+  task_title: String,
+  original_remind_at: i64,
+  snoozed_until: i64,
+}
+
+const PARAM_PLACEHOLDER: &str = "{param}";
+/// Everything except RFC 3986 unreserved characters is percent-encoded.
+const PARAM_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+const UNFILED_LIST_KEY: &str = "unfiled";
+const REQUIRED_TABLES: &[&str] = &["lists", "schemes", "tasks", "task_actions", "fired_reminders"];
+const SETTING_REPEAT_CATCH_UP: &str = "repeat_catch_up";
+/// How far back, in minutes, the startup pass looks for reminders missed while the
+/// app was closed. 0 turns the pass off.
+const SETTING_MISSED_REMINDER_WINDOW: &str = "missed_reminder_window_minutes";
+/// Which instant a wall-clock time repeated by a DST fall-back resolves to:
+/// "earliest" (the first pass) or "latest".
+const SETTING_DST_AMBIGUOUS: &str = "dst_ambiguous_time";
+/// How late, in minutes, a reminder may still fire after its time has passed.
+const SETTING_REMINDER_GRACE: &str = "reminder_grace_minutes";
+/// How many days fired-reminder records are kept for deduplication.
+const SETTING_FIRED_RETENTION: &str = "fired_reminder_retention_days";
+/// Opt-in for `shell` scheme actions, which run arbitrary commands.
+const SETTING_ALLOW_SHELL_ACTIONS: &str = "allow_shell_actions";
+const KNOWN_SETTINGS: &[(&str, &str)] = &[
+  (SETTING_REPEAT_CATCH_UP, "false"),
+  (SETTING_MISSED_REMINDER_WINDOW, "1440"),
+  (SETTING_REMINDER_GRACE, "10"),
+  (SETTING_FIRED_RETENTION, "30"),
+  (SETTING_DST_AMBIGUOUS, "earliest"),
+  (SETTING_ALLOW_SHELL_ACTIONS, "false"),
+];
+const MAX_REPEAT_CATCH_UP_STEPS: usize = 5000;
+/// Leads every encrypted backup file, followed by the salt, the nonce and the
+/// AES-256-GCM ciphertext of the JSON payload. Plain backups start with `{`.
+const ENCRYPTED_BACKUP_MAGIC: &[u8] = b"LINKFLOW-ENCRYPTED-BACKUP-1\n";
+const BACKUP_SALT_LEN: usize = 16;
+/// v2 added task priority and tags; v3 added task notes.
+const BACKUP_VERSION: u32 = 3;
+const BACKUP_NONCE_LEN: usize = 12;
+/// How long the scheduler waits before retrying after a failed database query.
+const SCHEDULER_RETRY_MS: u64 = 5000;
+/// Longest clock jump `zone_instant_ms` rolls across; zones have skipped a whole day.
+const MAX_DST_GAP_MINUTES: usize = 24 * 60;
+/// Action type the frontend registers with "complete" and "snooze" buttons.
+const REMINDER_ACTION_TYPE: &str = "task-reminder";
+const REMINDER_ACTION_SNOOZE_MINUTES: i64 = 10;
+/// How far back `undo_last_delete` reaches, and how many deletions the log keeps.
+const UNDO_WINDOW_MS: i64 = 10 * 60 * 1000;
+const UNDO_LOG_LIMIT: i64 = 50;
+const SNAPSHOT_CACHE_VERSION: u32 = 5;
+const SNAPSHOT_CACHE_DEBOUNCE_MS: u64 = 1500;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -54,6 +203,10 @@ struct ListItem {
   id: String,
   name: String,
   icon: String,
+  color: Option<String>,
+  /// Minutes-before-due reminder given to new timed tasks created without one.
+  #[serde(default)]
+  default_reminder_offset: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,8 +216,16 @@ struct UrlScheme {
   name: String,
   icon: String,
   template: String,
-  kind: String,
+  kind: SchemeKind,
   param_type: String,
+  /// Disabled schemes are hidden from pickers but still resolve for tasks
+  /// that are already bound to them.
+  #[serde(default = "default_true")]
+  enabled: bool,
+}
+
+fn default_true() -> bool {
+  true
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,21 +235,146 @@ struct TaskActionBinding {
   params: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What running a scheme's resolved template does. Unknown or legacy values read
+/// from the database or a backup fall back to `Url`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", from = "String")]
+enum SchemeKind {
+  /// Opens the URL with the system handler.
+  #[default]
+  Url,
+  /// Copies the filled-in template to the clipboard.
+  Clipboard,
+  /// Runs the filled-in template as a shell command; needs the
+  /// `allow_shell_actions` setting.
+  Shell,
+}
+
+impl SchemeKind {
+  fn to_db(self) -> &'static str {
+    match self {
+      SchemeKind::Url => "url",
+      SchemeKind::Clipboard => "clipboard",
+      SchemeKind::Shell => "shell",
+    }
+  }
+
+  fn parse(value: &str) -> Option<Self> {
+    match value {
+      "url" => Some(SchemeKind::Url),
+      "clipboard" => Some(SchemeKind::Clipboard),
+      "shell" => Some(SchemeKind::Shell),
+      _ => None,
+    }
+  }
+}
+
+impl From<String> for SchemeKind {
+  fn from(value: String) -> Self {
+    SchemeKind::parse(&value).unwrap_or_default()
+  }
+}
+
+/// Stored as 0/1/2 so SQL can order by it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Priority {
+  Low,
+  #[default]
+  Normal,
+  High,
+}
+
+impl Priority {
+  fn to_db(self) -> i64 {
+    match self {
+      Priority::Low => 0,
+      Priority::Normal => 1,
+      Priority::High => 2,
+    }
+  }
+
+  fn from_db(value: i64) -> Self {
+    match value {
+      0 => Priority::Low,
+      2 => Priority::High,
+      _ => Priority::Normal,
+    }
+  }
+
+  fn from_level(level: i64) -> Result<Self, String> {
+    match level {
+      0..=2 => Ok(Priority::from_db(level)),
+      _ => Err(format!("Priority must be between 0 and 2, got {level}")),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct RepeatRule {
   #[serde(rename = "type")]
   rule_type: String,
   day_of_week: Option<Vec<u8>>,
   day_of_month: Option<Vec<u8>>,
+  /// Monthly "nth weekday" mode: 1–5, or -1 for the last occurrence, combined
+  /// with the weekdays in `day_of_week` (e.g. 3 + [4] = third Thursday).
+  week_of_month: Option<i8>,
+  /// Repeat every N days/weeks/months; absent means 1.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  interval: Option<u32>,
+  /// Last date (YYYY-MM-DD) an occurrence may fall on.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  until: Option<String>,
+  /// Occurrences left including this one; each spawned instance carries one fewer.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  count: Option<u32>,
+  /// Monthly day-of-month policy for months too short for the day: "skip" (default)
+  /// or "last_day" to fall back to the month's last day.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  on_short_month: Option<String>,
+  /// Daily only: occurrences that would land on Saturday or Sunday move to Monday.
+  #[serde(default)]
+  skip_weekends: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// `relative` reminders fire `offset_minutes` before the task's due time;
+/// `after_completion_of` reminders fire `offset_minutes` after the task named by
+/// `depends_on_task_id` is completed, and stay unscheduled until then.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct Reminder {
   #[serde(rename = "type")]
   reminder_type: String,
+  #[serde(default)]
   offset_minutes: i64,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  depends_on_task_id: Option<String>,
+  /// ISO datetime for "absolute" reminders; local time unless it carries an offset.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  at: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct ReminderColumns {
+  enabled: Option<i64>,
+  reminder_type: Option<String>,
+  offset_minutes: Option<i64>,
+  depends_on: Option<String>,
+  at: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct RepeatColumns {
+  repeat_type: Option<String>,
+  day_of_week: Option<String>,
+  day_of_month: Option<String>,
+  week_of_month: Option<i64>,
+  interval: Option<i64>,
+  until: Option<String>,
+  count: Option<i64>,
+  on_short_month: Option<String>,
+  skip_weekends: i64,
 }
 
 fn deserialize_reminder<'de, D>(deserializer: D) -> Result<Option<Reminder>, D::Error>
@@ -103,6 +389,7 @@ where
         Ok(Some(Reminder {
           reminder_type: "relative".to_string(),
           offset_minutes: 10,
+          ..Reminder::default()
         }))
       } else {
         Ok(None)
@@ -114,7 +401,7 @@ where
   }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct TaskItem {
   id: String,
@@ -122,6 +409,7 @@ struct TaskItem {
   title: String,
   detail: Option<String>,
   completed: bool,
+  completed_at: Option<i64>,
   due_date: Option<String>,
   time: Option<String>,
   #[serde(default, deserialize_with = "deserialize_reminder")]
@@ -129,6 +417,22 @@ struct TaskItem {
   #[serde(rename = "repeat")]
   repeat_rule: Option<RepeatRule>,
   actions: Option<Vec<TaskActionBinding>>,
+  #[serde(default)]
+  priority: Priority,
+  #[serde(default)]
+  tags: Vec<String>,
+  /// IANA zone the task's date and time are written in; `None` follows the system zone.
+  #[serde(default)]
+  timezone: Option<String>,
+  /// Lower-case `#rgb` / `#rrggbb` flag color, validated like list colors.
+  #[serde(default)]
+  color: Option<String>,
+  /// RFC 3339 timestamps, always in UTC (`2024-05-01T08:30:00Z`). Read-only:
+  /// edits ignore them, while backup restores and snapshot saves keep them.
+  #[serde(default)]
+  created_at: Option<String>,
+  #[serde(default)]
+  updated_at: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -139,12 +443,97 @@ struct AppSnapshot {
   schemes: Vec<UrlScheme>,
 }
 
+/// On-disk copy of the last snapshot, served before the database is queried.
+/// Bump `SNAPSHOT_CACHE_VERSION` whenever `AppSnapshot` changes shape.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SnapshotCache {
+  cache_version: u32,
+  app_version: String,
+  written_at: i64,
+  snapshot: AppSnapshot,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct BackupPayload {
   version: u32,
   exported_at: String,
   snapshot: AppSnapshot,
+  #[serde(default)]
+  notes: Vec<TaskNote>,
+}
+
+/// Long-form notes live apart from `TaskItem::detail` so list loads stay light;
+/// they're only read on demand and in backups.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskNote {
+  task_id: String,
+  body: String,
+}
+
+/// The thresholds the scheduler is running with, resolved from settings. It sleeps
+/// until the next reminder or a wakeup rather than polling, so the retry delay after
+/// a failed query is its only fixed interval.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SchedulerConfig {
+  grace_ms: i64,
+  retention_ms: i64,
+  missed_reminder_window_ms: i64,
+  retry_interval_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CompletionRate {
+  due: i64,
+  completed: i64,
+  /// `completed / due`, or 0 when nothing was due.
+  rate: f64,
+}
+
+/// A button pressed on a reminder notification.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ReminderAction {
+  Complete,
+  Snooze,
+}
+
+/// Which instant `zone_instant_ms` picks when a wall-clock time occurs twice.
+#[derive(Debug, Clone, Copy, Default)]
+enum DstAmbiguity {
+  #[default]
+  Earliest,
+  Latest,
+}
+
+/// `Replace` wipes the database before restoring; `Merge` upserts by id and keeps
+/// rows the backup doesn't mention.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ImportMode {
+  #[default]
+  Replace,
+  Merge,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MergeCounts {
+  added: usize,
+  updated: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupImportResult {
+  snapshot: AppSnapshot,
+  lists: MergeCounts,
+  schemes: MergeCounts,
+  tasks: MergeCounts,
 }
 
 #[derive(Debug, Deserialize)]
@@ -152,6 +541,16 @@ struct BackupPayload {
 struct ListInput {
   name: String,
   icon: String,
+  color: Option<String>,
+  default_reminder_offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListStyleUpdate {
+  id: String,
+  icon: Option<String>,
+  color: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -162,6 +561,181 @@ struct SchemeInput {
   template: String,
   kind: Option<String>,
   param_type: String,
+  enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SchemeSupport {
+  scheme: UrlScheme,
+  platform: String,
+  known: bool,
+  supported: bool,
+}
+
+/// Task count and approximate text size for one list, or the unfiled bucket.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ListStorage {
+  list_id: String,
+  list_name: Option<String>,
+  task_count: i64,
+  approx_bytes: i64,
+}
+
+/// A reusable task shape without a date or time. Reminder, repeat rule and actions are
+/// stored as JSON since templates are never queried by them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskTemplate {
+  id: String,
+  name: String,
+  list_id: Option<String>,
+  title: String,
+  detail: Option<String>,
+  reminder: Option<Reminder>,
+  #[serde(rename = "repeat")]
+  repeat_rule: Option<RepeatRule>,
+  actions: Vec<TaskActionBinding>,
+  created_at: i64,
+}
+
+/// Optional criteria for `tasks_filtered`; absent fields don't constrain the result.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskFilter {
+  /// A list id, or `UNFILED_LIST_KEY` for tasks without a list.
+  list_id: Option<String>,
+  completed: Option<bool>,
+  /// Inclusive YYYY-MM-DD bounds on the due date.
+  due_from: Option<String>,
+  due_to: Option<String>,
+  priority_min: Option<Priority>,
+  /// Matched case-insensitively.
+  tag: Option<String>,
+}
+
+/// What `import_tasks_resolved` does with an input matching an existing title and date.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ImportConflict {
+  Skip,
+  Replace,
+  Duplicate,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportReport {
+  created: usize,
+  replaced: usize,
+  skipped: usize,
+}
+
+/// How `bulk_toggle_completed` spawns next instances of repeating tasks it completes.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RecurrenceSpawn {
+  /// One next instance per completed task, as a single toggle would.
+  #[default]
+  Each,
+  /// No next instances at all.
+  Skip,
+  /// One next instance per series, dated on or after today.
+  Consolidated,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskIssue {
+  task_id: String,
+  message: String,
+}
+
+/// A task action whose param count no longer matches its scheme's `{param}` placeholders.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ActionMismatch {
+  task_id: String,
+  task_title: String,
+  position: i64,
+  scheme_id: String,
+  scheme_name: String,
+  expected: usize,
+  actual: usize,
+}
+
+/// What `rebuild` repaired, and what it found but left for the user to fix.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RebuildReport {
+  integrity_problems: Vec<String>,
+  schemes_normalized: usize,
+  invalid_repeat_rules: Vec<TaskIssue>,
+  invalid_reminders: Vec<TaskIssue>,
+  unschedulable_reminders: Vec<String>,
+  action_param_mismatches: Vec<ActionMismatch>,
+}
+
+/// A row whose foreign key points at a missing parent, from `PRAGMA foreign_key_check`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ForeignKeyViolation {
+  table: String,
+  rowid: Option<i64>,
+  parent: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IntegrityReport {
+  ok: bool,
+  problems: Vec<String>,
+  foreign_key_violations: Vec<ForeignKeyViolation>,
+}
+
+/// Database file size in bytes before and after `compact_database`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CompactResult {
+  size_before: u64,
+  size_after: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ActionTarget {
+  kind: SchemeKind,
+  value: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ShellActionOutput {
+  exit_code: Option<i32>,
+  stdout: String,
+  stderr: String,
+}
+
+/// Result of `validate_scheme_params`. `param_errors` lines up with the params
+/// passed in, holding `None` for each one that is acceptable or unused.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SchemeParamCheck {
+  ok: bool,
+  count_error: Option<String>,
+  param_errors: Vec<Option<String>>,
+}
+
+/// One task action with its template filled in, or the reason it could not be.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResolvedAction {
+  index: usize,
+  scheme_id: String,
+  kind: Option<SchemeKind>,
+  url: Option<String>,
+  error: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -177,6 +751,13 @@ struct NewTaskInput {
   #[serde(rename = "repeat")]
   repeat_rule: Option<RepeatRule>,
   actions: Option<Vec<TaskActionBinding>>,
+  priority: Option<Priority>,
+  tags: Option<Vec<String>>,
+  timezone: Option<String>,
+  color: Option<String>,
+  /// Set when the user explicitly chose no reminder, so the list default isn't applied.
+  #[serde(default)]
+  skip_default_reminder: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -194,10 +775,50 @@ struct SaveTaskInput {
   #[serde(rename = "repeat")]
   repeat_rule: Option<RepeatRule>,
   actions: Option<Vec<TaskActionBinding>>,
+  priority: Option<Priority>,
+  tags: Option<Vec<String>>,
+  /// `None` keeps the stored zone; an empty string clears it.
+  timezone: Option<String>,
+  /// `None` keeps the stored color; an empty string clears it.
+  color: Option<String>,
+}
+
+/// Validates a rule and returns it with weekday and month-day lists sorted and de-duplicated.
+fn normalize_repeat_rule(rule: &Option<RepeatRule>) -> Result<Option<RepeatRule>, String> {
+  validate_repeat_rule(rule)?;
+  let sorted_unique = |days: &Option<Vec<u8>>| {
+    days.clone().map(|mut days| {
+      days.sort_unstable();
+      days.dedup();
+      days
+    })
+  };
+  Ok(rule.as_ref().map(|rule| RepeatRule {
+    day_of_week: sorted_unique(&rule.day_of_week),
+    day_of_month: sorted_unique(&rule.day_of_month),
+    ..rule.clone()
+  }))
 }
 
 fn validate_repeat_rule(rule: &Option<RepeatRule>) -> Result<(), String> {
   if let Some(rule) = rule {
+    if rule.interval == Some(0) {
+      return Err("Repeat interval must be at least 1".to_string());
+    }
+    if rule.count == Some(0) {
+      return Err("Repeat count must be at least 1".to_string());
+    }
+    if !matches!(rule.on_short_month.as_deref(), None | Some("skip") | Some("last_day")) {
+      return Err("Short-month policy must be skip or last_day".to_string());
+    }
+    if rule.skip_weekends && rule.rule_type != "daily" {
+      return Err("Skipping weekends only applies to daily repeats".to_string());
+    }
+    if let Some(until) = rule.until.as_deref() {
+      if parse_date_ymd(until).is_none() {
+        return Err("Repeat end date must be YYYY-MM-DD".to_string());
+      }
+    }
     match rule.rule_type.as_str() {
       "daily" => Ok(()),
       "weekly" => {
@@ -210,6 +831,20 @@ fn validate_repeat_rule(rule: &Option<RepeatRule>) -> Result<(), String> {
         }
         Ok(())
       }
+      "monthly" if rule.week_of_month.is_some() => {
+        let ordinal = rule.week_of_month.unwrap_or_default();
+        if ordinal != -1 && !(1..=5).contains(&ordinal) {
+          return Err("Monthly week ordinal must be between 1 and 5, or -1 for the last week".to_string());
+        }
+        let days = rule.day_of_week.clone().unwrap_or_default();
+        if days.is_empty() {
+          return Err("Monthly weekday repeat must contain at least one weekday".to_string());
+        }
+        if days.iter().any(|day| *day > 6) {
+          return Err("Monthly weekday repeat day must be between 0 and 6".to_string());
+        }
+        Ok(())
+      }
       "monthly" => {
         let days = rule.day_of_month.clone().unwrap_or_default();
         if days.is_empty() {
@@ -227,141 +862,691 @@ fn validate_repeat_rule(rule: &Option<RepeatRule>) -> Result<(), String> {
   }
 }
 
-fn normalize_relative_reminder(reminder: &Option<Reminder>) -> Result<Option<Reminder>, String> {
-  if let Some(value) = reminder {
-    if value.reminder_type != "relative" {
-      return Err("Only relative reminders are supported".to_string());
-    }
-    return Ok(Some(Reminder {
+fn normalize_reminder(reminder: &Option<Reminder>) -> Result<Option<Reminder>, String> {
+  let Some(value) = reminder else {
+    return Ok(None);
+  };
+
+  match value.reminder_type.as_str() {
+    "relative" => Ok(Some(Reminder {
       reminder_type: "relative".to_string(),
       offset_minutes: value.offset_minutes.max(0),
-    }));
+      ..Reminder::default()
+    })),
+    "absolute" => {
+      let at = value
+        .at
+        .as_deref()
+        .map(str::trim)
+        .filter(|at| !at.is_empty())
+        .ok_or_else(|| "An absolute reminder requires a date and time".to_string())?;
+      if parse_reminder_instant(at).is_none() {
+        return Err(format!("Invalid absolute reminder time: {at}"));
+      }
+      Ok(Some(Reminder {
+        reminder_type: "absolute".to_string(),
+        at: Some(at.to_string()),
+        ..Reminder::default()
+      }))
+    }
+    "after_completion_of" => {
+      let depends_on = value
+        .depends_on_task_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| "An after-completion reminder requires a task to depend on".to_string())?;
+      Ok(Some(Reminder {
+        reminder_type: "after_completion_of".to_string(),
+        offset_minutes: value.offset_minutes.max(0),
+        depends_on_task_id: Some(depends_on.to_string()),
+        at: None,
+      }))
+    }
+    _ => Err("Unsupported reminder type".to_string()),
   }
-  Ok(None)
 }
 
-fn reminder_to_db(reminder: &Option<Reminder>) -> Result<(Option<i64>, Option<i64>), String> {
-  let normalized = normalize_relative_reminder(reminder)?;
-  if let Some(value) = normalized {
-    return Ok((Some(1), Some(value.offset_minutes)));
-  }
-  Ok((None, None))
+fn reminder_to_db(reminder: &Option<Reminder>) -> Result<ReminderColumns, String> {
+  let Some(value) = normalize_reminder(reminder)? else {
+    return Ok(ReminderColumns::default());
+  };
+  Ok(ReminderColumns {
+    enabled: Some(1),
+    reminder_type: Some(value.reminder_type),
+    offset_minutes: Some(value.offset_minutes),
+    depends_on: value.depends_on_task_id,
+    at: value.at,
+  })
 }
 
-fn reminder_from_db(enabled: Option<i64>, offset: Option<i64>) -> Option<Reminder> {
+fn reminder_from_db(
+  enabled: Option<i64>,
+  reminder_type: Option<String>,
+  offset: Option<i64>,
+  depends_on: Option<String>,
+  at: Option<String>,
+) -> Option<Reminder> {
   if enabled.unwrap_or(0) == 0 {
     return None;
   }
-  Some(Reminder {
-    reminder_type: "relative".to_string(),
-    offset_minutes: offset.unwrap_or(10).max(0),
+  let offset_minutes = offset.unwrap_or(10).max(0);
+  match (reminder_type.as_deref(), depends_on, at) {
+    (Some("after_completion_of"), Some(depends_on), _) => Some(Reminder {
+      reminder_type: "after_completion_of".to_string(),
+      offset_minutes,
+      depends_on_task_id: Some(depends_on),
+      at: None,
+    }),
+    (Some("absolute"), _, Some(at)) => Some(Reminder {
+      reminder_type: "absolute".to_string(),
+      at: Some(at),
+      ..Reminder::default()
+    }),
+    _ => Some(Reminder {
+      reminder_type: "relative".to_string(),
+      offset_minutes,
+      ..Reminder::default()
+    }),
+  }
+}
+
+fn repeat_to_db(rule: &Option<RepeatRule>) -> Result<RepeatColumns, String> {
+  let Some(rule) = rule else {
+    return Ok(RepeatColumns::default());
+  };
+  Ok(RepeatColumns {
+    repeat_type: Some(rule.rule_type.clone()),
+    day_of_week: rule
+      .day_of_week
+      .as_ref()
+      .map(serde_json::to_string)
+      .transpose()
+      .map_err(|err| format!("Failed to encode repeat days of week: {err}"))?,
+    day_of_month: rule
+      .day_of_month
+      .as_ref()
+      .map(serde_json::to_string)
+      .transpose()
+      .map_err(|err| format!("Failed to encode repeat days of month: {err}"))?,
+    week_of_month: rule.week_of_month.map(i64::from),
+    interval: rule.interval.map(i64::from),
+    until: rule.until.clone(),
+    count: rule.count.map(i64::from),
+    on_short_month: rule.on_short_month.clone(),
+    skip_weekends: i64::from(rule.skip_weekends),
   })
 }
 
-fn normalize_scheme_kind(kind: Option<String>) -> String {
-  let _ = kind;
-  "url".to_string()
+fn normalize_detail(detail: Option<String>) -> Option<String> {
+  detail.and_then(|value| {
+    let trimmed = value.trim().to_string();
+    if trimmed.is_empty() { None } else { Some(trimmed) }
+  })
 }
 
-fn open_connection(db_path: &Path) -> Result<Connection, String> {
-  let conn = Connection::open(db_path).map_err(|err| format!("Failed to open database: {err}"))?;
-  conn
-    .pragma_update(None, "foreign_keys", "ON")
-    .map_err(|err| format!("Failed to enable foreign keys: {err}"))?;
-  Ok(conn)
+fn normalize_list_icon(icon: &str) -> String {
+  let icon = icon.trim();
+  if icon.is_empty() { "🗂️".to_string() } else { icon.to_string() }
 }
 
-fn default_lists() -> Vec<ListItem> {
-  vec![
-    ListItem {
-      id: "list_today".to_string(),
-      name: "所有任务".to_string(),
-      icon: "📋".to_string(),
-    },
-    ListItem {
-      id: "list_work".to_string(),
-      name: "工作".to_string(),
-      icon: "💼".to_string(),
-    },
-    ListItem {
-      id: "list_life".to_string(),
-      name: "生活".to_string(),
-      icon: "🏡".to_string(),
-    },
-  ]
+/// Accepts `#RGB` or `#RRGGBB` (case-insensitive) and returns it lowercased.
+/// Blank input clears the color.
+fn normalize_hex_color(color: Option<&str>) -> Result<Option<String>, String> {
+  let Some(value) = color.map(str::trim).filter(|value| !value.is_empty()) else {
+    return Ok(None);
+  };
+  let digits = value.strip_prefix('#').unwrap_or("");
+  let valid = matches!(digits.len(), 3 | 6) && digits.chars().all(|ch| ch.is_ascii_hexdigit());
+  if !valid {
+    return Err(format!("Invalid color \"{value}\": expected a hex code like #RRGGBB"));
+  }
+  Ok(Some(value.to_ascii_lowercase()))
 }
 
-fn default_schemes() -> Vec<UrlScheme> {
-  vec![
-    UrlScheme {
-      id: "scheme_wemeet".to_string(),
-      name: "腾讯会议".to_string(),
+/// Validates a requested kind; a missing or blank one means `url`.
+fn normalize_scheme_kind(kind: Option<String>) -> Result<SchemeKind, String> {
+  match kind.as_deref().map(str::trim).filter(|value| !value.is_empty()) {
+    None => Ok(SchemeKind::Url),
+    Some(value) => SchemeKind::parse(value).ok_or_else(|| format!("Unsupported scheme kind: {value}")),
+  }
+}
+
+const ALL_PLATFORMS: &[&str] = &["macos", "windows", "linux", "ios", "android"];
+
+/// Best-effort map of URL scheme prefixes to the platforms with a handler for them.
+const KNOWN_SCHEME_PLATFORMS: &[(&str, &[&str])] = &[
+  ("http", ALL_PLATFORMS),
+  ("https", ALL_PLATFORMS),
+  ("mailto", ALL_PLATFORMS),
+  ("tel", &["macos", "ios", "android"]),
+  ("message", &["macos", "ios"]),
+  ("iosamap", &["ios"]),
+  ("weixin", &["macos", "windows", "ios", "android"]),
+  ("wemeet", &["macos", "windows", "ios", "android"]),
+  ("zhihu", &["ios", "android"]),
+];
+
+fn current_platform() -> &'static str {
+  if cfg!(target_os = "macos") {
+    "macos"
+  } else if cfg!(target_os = "windows") {
+    "windows"
+  } else if cfg!(target_os = "ios") {
+    "ios"
+  } else if cfg!(target_os = "android") {
+    "android"
+  } else if cfg!(target_os = "linux") {
+    "linux"
+  } else {
+    "unknown"
+  }
+}
+
+fn scheme_prefix(template: &str) -> Option<String> {
+  let (prefix, _) = template.trim().split_once(':')?;
+  if prefix.is_empty() {
+    return None;
+  }
+  Some(prefix.to_ascii_lowercase())
+}
+
+/// Unknown prefixes are assumed supported so custom schemes are never hidden.
+fn scheme_support(scheme: UrlScheme, platform: &str) -> SchemeSupport {
+  let platforms = scheme_prefix(&scheme.template).and_then(|prefix| {
+    KNOWN_SCHEME_PLATFORMS
+      .iter()
+      .find(|(known, _)| *known == prefix)
+      .map(|(_, platforms)| *platforms)
+  });
+
+  SchemeSupport {
+    scheme,
+    platform: platform.to_string(),
+    known: platforms.is_some(),
+    supported: platforms.map(|items| items.contains(&platform)).unwrap_or(true),
+  }
+}
+
+fn count_template_params(template: &str) -> usize {
+  template.matches(PARAM_PLACEHOLDER).count()
+}
+
+fn normalize_param_type(param_type: &str) -> Result<String, String> {
+  match param_type.trim() {
+    value @ ("number" | "string") => Ok(value.to_string()),
+    other => Err(format!("Unsupported scheme param type \"{other}\"; expected number or string")),
+  }
+}
+
+/// Rejects bindings with fewer params than the scheme has `{param}` placeholders;
+/// extra params are ignored when the URL is rendered.
+fn validate_action_params(conn: &Connection, action: &TaskActionBinding) -> Result<(), String> {
+  let (name, template): (String, String) = conn
+    .query_row(
+      "SELECT name, template FROM schemes WHERE id = ?1",
+      params![action.scheme_id],
+      |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .map_err(|err| match err {
+      rusqlite::Error::QueryReturnedNoRows => format!("Scheme not found: {}", action.scheme_id),
+      other => format!("Failed to load scheme for action: {other}"),
+    })?;
+
+  let expected = count_template_params(&template);
+  if action.params.len() < expected {
+    return Err(format!(
+      "{name} needs {expected} parameter(s) but the action provides {}",
+      action.params.len()
+    ));
+  }
+  Ok(())
+}
+
+/// Checks the bindings on user-supplied task input. Stored rows are written back
+/// as-is (recurrences, backups, undo) even when a scheme has since changed, so this
+/// is only called where the actions come from the user.
+fn validate_task_actions(conn: &Connection, task: &TaskItem) -> Result<(), String> {
+  for action in task.actions.as_deref().unwrap_or_default() {
+    validate_action_params(conn, action)?;
+  }
+  Ok(())
+}
+
+fn encode_action_param(scheme: &UrlScheme, index: usize, value: &str) -> Result<String, String> {
+  let value = value.trim();
+  if scheme.param_type == "number" {
+    if value.is_empty() || !value.chars().all(|ch| ch.is_ascii_digit()) {
+      return Err(format!("Parameter {} of {} must be a number", index + 1, scheme.name));
+    }
+    return Ok(value.to_string());
+  }
+  Ok(match scheme.kind {
+    SchemeKind::Url => utf8_percent_encode(value, PARAM_ENCODE_SET).to_string(),
+    SchemeKind::Clipboard => value.to_string(),
+    SchemeKind::Shell => shell_quote(value).ok_or_else(|| {
+      format!(
+        "Parameter {} of {} contains characters that cannot be passed to the shell safely",
+        index + 1,
+        scheme.name
+      )
+    })?,
+  })
+}
+
+/// Quotes a param as a single word for the platform shell `run_shell_action` uses,
+/// or `None` when the value cannot be passed through that shell safely.
+fn shell_quote(value: &str) -> Option<String> {
+  if cfg!(windows) {
+    cmd_quote(value)
+  } else {
+    Some(posix_quote(value))
+  }
+}
+
+/// Single-quotes for `sh -c`, where nothing inside single quotes is special.
+fn posix_quote(value: &str) -> String {
+  format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Double-quotes for `cmd /C`. cmd has no quoting that disables `%` expansion or
+/// reliably nests `"`, so values containing metacharacters are refused outright.
+fn cmd_quote(value: &str) -> Option<String> {
+  const UNSAFE: &[char] = &['"', '%', '^', '&', '|', '<', '>', '!', '\r', '\n'];
+  if value.contains(UNSAFE) {
+    return None;
+  }
+  Some(format!("\"{value}\""))
+}
+
+/// Substitutes each `{param}` in the template, in order, with the next bound param,
+/// encoded for the scheme's kind.
+fn render_action_url(scheme: &UrlScheme, params: &[String]) -> Result<String, String> {
+  let mut pieces = scheme.template.split(PARAM_PLACEHOLDER);
+  let mut url = pieces.next().unwrap_or_default().to_string();
+  for (index, piece) in pieces.enumerate() {
+    let value = params
+      .get(index)
+      .ok_or_else(|| format!("{} needs more parameters than the action provides", scheme.name))?;
+    url.push_str(&encode_action_param(scheme, index, value)?);
+    url.push_str(piece);
+  }
+  Ok(url)
+}
+
+fn resolve_task_actions(task: &TaskItem, schemes: &HashMap<String, UrlScheme>) -> Vec<ResolvedAction> {
+  task
+    .actions
+    .as_deref()
+    .unwrap_or_default()
+    .iter()
+    .enumerate()
+    .map(|(index, action)| {
+      let scheme = schemes.get(&action.scheme_id);
+      let result = scheme
+        .ok_or_else(|| "Scheme not found".to_string())
+        .and_then(|scheme| render_action_url(scheme, &action.params));
+      ResolvedAction {
+        index,
+        scheme_id: action.scheme_id.clone(),
+        kind: scheme.map(|scheme| scheme.kind),
+        url: result.as_ref().ok().cloned(),
+        error: result.err(),
+      }
+    })
+    .collect()
+}
+
+/// How long a connection waits on another writer's lock before failing with
+/// "database is locked".
+const DB_BUSY_TIMEOUT_MS: u64 = 5000;
+
+/// Opens the database in WAL mode so the scheduler and UI commands can read
+/// while another connection writes, with a busy timeout to queue concurrent
+/// writers instead of failing them. WAL keeps `linkflow.db-wal` and
+/// `linkflow.db-shm` next to the database in the app data dir; the database
+/// file alone may lag behind recent commits until a checkpoint, which is why
+/// anything copying the raw file checkpoints first. `synchronous = NORMAL` can
+/// lose the last commits on power loss (never corrupting the file), which
+/// suits a local task list.
+fn open_connection(db_path: &Path) -> Result<Connection, String> {
+  let conn = Connection::open(db_path).map_err(|err| format!("Failed to open database: {err}"))?;
+  conn
+    .busy_timeout(std::time::Duration::from_millis(DB_BUSY_TIMEOUT_MS))
+    .map_err(|err| format!("Failed to set busy timeout: {err}"))?;
+  let journal_mode = conn
+    .pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+    .map_err(|err| format!("Failed to enable WAL mode: {err}"))?;
+  // SQLite answers with the mode it actually uses; some filesystems (network
+  // shares, for one) cannot do WAL and stay in rollback-journal mode.
+  if !journal_mode.eq_ignore_ascii_case("wal") {
+    eprintln!(
+      "database {} is using journal mode {journal_mode} instead of WAL; concurrent access will block more",
+      db_path.display()
+    );
+  }
+  conn
+    .pragma_update(None, "synchronous", "NORMAL")
+    .map_err(|err| format!("Failed to set synchronous mode: {err}"))?;
+  conn
+    .pragma_update(None, "foreign_keys", "ON")
+    .map_err(|err| format!("Failed to enable foreign keys: {err}"))?;
+  Ok(conn)
+}
+
+/// A command that panics while holding the lock poisons the mutex, but the connection
+/// is still sound (a dropped transaction rolls back), so the guard is recovered
+/// instead of failing every later command until restart.
+fn lock_db(db: &DbState) -> Result<MutexGuard<'_, Connection>, String> {
+  Ok(db.conn.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<(), String> {
+  let exists: i64 = conn
+    .query_row(
+      "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+      params![table, column],
+      |row| row.get(0),
+    )
+    .map_err(|err| format!("Failed to inspect {table} columns: {err}"))?;
+  if exists == 0 {
+    conn
+      .execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"))
+      .map_err(|err| format!("Failed to add {table}.{column}: {err}"))?;
+  }
+  Ok(())
+}
+
+/// Returns the problems reported by `PRAGMA integrity_check`; empty means ok.
+fn run_integrity_check(conn: &Connection) -> Result<Vec<String>, String> {
+  let mut stmt = conn
+    .prepare("PRAGMA integrity_check")
+    .map_err(|err| format!("Failed to run integrity check: {err}"))?;
+  let rows = stmt
+    .query_map([], |row| row.get::<_, String>(0))
+    .map_err(|err| format!("Failed to read integrity check: {err}"))?;
+
+  let mut problems = Vec::new();
+  for row in rows {
+    let message = row.map_err(|err| format!("Failed to read integrity check row: {err}"))?;
+    if message != "ok" {
+      problems.push(message);
+    }
+  }
+  Ok(problems)
+}
+
+fn run_foreign_key_check(conn: &Connection) -> Result<Vec<ForeignKeyViolation>, String> {
+  let mut stmt = conn
+    .prepare("PRAGMA foreign_key_check")
+    .map_err(|err| format!("Failed to run foreign key check: {err}"))?;
+  let rows = stmt
+    .query_map([], |row| {
+      Ok(ForeignKeyViolation {
+        table: row.get(0)?,
+        rowid: row.get(1)?,
+        parent: row.get(2)?,
+      })
+    })
+    .map_err(|err| format!("Failed to read foreign key check: {err}"))?;
+
+  let mut violations = Vec::new();
+  for row in rows {
+    violations.push(row.map_err(|err| format!("Failed to read foreign key check row: {err}"))?);
+  }
+  Ok(violations)
+}
+
+fn schema_user_version(conn: &Connection) -> Result<i64, String> {
+  conn
+    .query_row("PRAGMA user_version", [], |row| row.get(0))
+    .map_err(|err| format!("Failed to read schema version: {err}"))
+}
+
+/// Checks that `source` is a healthy LinkFlow database this build can open.
+fn validate_restore_source(source: &Path, current_version: i64) -> Result<(), String> {
+  let conn = Connection::open_with_flags(source, OpenFlags::SQLITE_OPEN_READ_ONLY)
+    .map_err(|err| format!("Failed to open database file: {err}"))?;
+
+  let problems = run_integrity_check(&conn)?;
+  if !problems.is_empty() {
+    return Err(format!("Database file failed integrity check: {}", problems.join("; ")));
+  }
+
+  for table in REQUIRED_TABLES {
+    let exists: i64 = conn
+      .query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+        params![table],
+        |row| row.get(0),
+      )
+      .map_err(|err| format!("Failed to inspect database file: {err}"))?;
+    if exists == 0 {
+      return Err(format!("Database file is missing the {table} table"));
+    }
+  }
+
+  if schema_user_version(&conn)? > current_version {
+    return Err("Database file was created by a newer version of LinkFlow".to_string());
+  }
+  Ok(())
+}
+
+fn setting_default(key: &str) -> Option<&'static str> {
+  KNOWN_SETTINGS
+    .iter()
+    .find(|(known, _)| *known == key)
+    .map(|(_, default)| *default)
+}
+
+/// Validates a value for a known setting and returns its canonical form.
+fn normalize_setting_value(key: &str, value: &str) -> Result<String, String> {
+  let value = value.trim();
+  match key {
+    SETTING_REPEAT_CATCH_UP | SETTING_ALLOW_SHELL_ACTIONS => match value {
+      "true" | "false" => Ok(value.to_string()),
+      _ => Err(format!("Setting {key} must be true or false")),
+    },
+    SETTING_MISSED_REMINDER_WINDOW => value
+      .parse::<u32>()
+      .map(|minutes| minutes.to_string())
+      .map_err(|_| format!("Setting {key} must be a non-negative whole number of minutes")),
+    SETTING_DST_AMBIGUOUS => match value {
+      "earliest" | "latest" => Ok(value.to_string()),
+      _ => Err(format!("Setting {key} must be earliest or latest")),
+    },
+    SETTING_REMINDER_GRACE | SETTING_FIRED_RETENTION => value
+      .parse::<u32>()
+      .ok()
+      .filter(|amount| *amount > 0)
+      .map(|amount| amount.to_string())
+      .ok_or_else(|| format!("Setting {key} must be a positive whole number")),
+    _ => Err(format!("Unknown setting: {key}")),
+  }
+}
+
+/// Returns the stored value, or the built-in default when the key was never set.
+fn read_setting(conn: &Connection, key: &str) -> Result<String, String> {
+  let default = setting_default(key).ok_or_else(|| format!("Unknown setting: {key}"))?;
+  let stored: Option<String> = conn
+    .query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0))
+    .map(Some)
+    .or_else(|err| match err {
+      rusqlite::Error::QueryReturnedNoRows => Ok(None),
+      other => Err(other),
+    })
+    .map_err(|err| format!("Failed to read setting {key}: {err}"))?;
+  Ok(stored.unwrap_or_else(|| default.to_string()))
+}
+
+fn read_bool_setting(conn: &Connection, key: &str) -> Result<bool, String> {
+  Ok(read_setting(conn, key)? == "true")
+}
+
+fn read_u32_setting(conn: &Connection, key: &str) -> Result<u32, String> {
+  let value = read_setting(conn, key)?;
+  value
+    .parse()
+    .map_err(|_| format!("Setting {key} has an invalid value: {value}"))
+}
+
+/// Read on every scheduler pass rather than cached, so a changed setting applies
+/// as soon as the loop is woken.
+fn reminder_grace_ms(conn: &Connection) -> Result<i64, String> {
+  Ok(i64::from(read_u32_setting(conn, SETTING_REMINDER_GRACE)?) * 60 * 1000)
+}
+
+fn read_dst_ambiguity(conn: &Connection) -> Result<DstAmbiguity, String> {
+  Ok(match read_setting(conn, SETTING_DST_AMBIGUOUS)?.as_str() {
+    "latest" => DstAmbiguity::Latest,
+    _ => DstAmbiguity::Earliest,
+  })
+}
+
+fn fired_retention_ms(conn: &Connection) -> Result<i64, String> {
+  Ok(i64::from(read_u32_setting(conn, SETTING_FIRED_RETENTION)?) * 24 * 60 * 60 * 1000)
+}
+
+fn write_setting(conn: &Connection, key: &str, value: &str) -> Result<String, String> {
+  let value = normalize_setting_value(key, value)?;
+  conn
+    .execute(
+      "INSERT INTO settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+      params![key, value],
+    )
+    .map_err(|err| format!("Failed to write setting {key}: {err}"))?;
+  Ok(value)
+}
+
+fn default_lists() -> Vec<ListItem> {
+  vec![
+    ListItem {
+      id: "list_today".to_string(),
+      name: "所有任务".to_string(),
+      icon: "📋".to_string(),
+      color: None,
+      default_reminder_offset: None,
+    },
+    ListItem {
+      id: "list_work".to_string(),
+      name: "工作".to_string(),
+      icon: "💼".to_string(),
+      color: None,
+      default_reminder_offset: None,
+    },
+    ListItem {
+      id: "list_life".to_string(),
+      name: "生活".to_string(),
+      icon: "🏡".to_string(),
+      color: None,
+      default_reminder_offset: None,
+    },
+  ]
+}
+
+fn default_schemes() -> Vec<UrlScheme> {
+  vec![
+    UrlScheme {
+      id: "scheme_wemeet".to_string(),
+      name: "腾讯会议".to_string(),
       icon: "📹".to_string(),
       template: "wemeet://inmeeting?code={param}".to_string(),
-      kind: "url".to_string(),
+      kind: SchemeKind::Url,
       param_type: "number".to_string(),
+      enabled: true,
     },
     UrlScheme {
       id: "scheme_mail".to_string(),
       name: "邮件".to_string(),
       icon: "✉️".to_string(),
       template: "mailto:{param}?subject={param}".to_string(),
-      kind: "url".to_string(),
+      kind: SchemeKind::Url,
       param_type: "string".to_string(),
+      enabled: true,
     },
     UrlScheme {
       id: "scheme_maps".to_string(),
       name: "高德地图".to_string(),
       icon: "🗺️".to_string(),
       template: "iosamap://path?sourceApplication=linkflow&dname={param}".to_string(),
-      kind: "url".to_string(),
+      kind: SchemeKind::Url,
       param_type: "string".to_string(),
+      enabled: true,
     },
     UrlScheme {
       id: "scheme_weixin_scanqrcode".to_string(),
       name: "微信-扫一扫".to_string(),
       icon: "🟢".to_string(),
       template: "weixin://scanqrcode".to_string(),
-      kind: "url".to_string(),
+      kind: SchemeKind::Url,
       param_type: "string".to_string(),
+      enabled: true,
     },
     UrlScheme {
       id: "scheme_zhihu_search".to_string(),
       name: "知乎-搜索".to_string(),
       icon: "🔎".to_string(),
       template: "zhihu://search?q={param}".to_string(),
-      kind: "url".to_string(),
+      kind: SchemeKind::Url,
       param_type: "string".to_string(),
+      enabled: true,
     },
     UrlScheme {
       id: "scheme_macos_tel".to_string(),
       name: "macos-电话".to_string(),
       icon: "📞".to_string(),
       template: "tel://{param}".to_string(),
-      kind: "url".to_string(),
+      kind: SchemeKind::Url,
       param_type: "number".to_string(),
+      enabled: true,
     },
     UrlScheme {
       id: "scheme_macos_message".to_string(),
       name: "macos-邮件".to_string(),
       icon: "📨".to_string(),
       template: "message://".to_string(),
-      kind: "url".to_string(),
+      kind: SchemeKind::Url,
       param_type: "string".to_string(),
+      enabled: true,
     },
   ]
 }
 
-fn init_database(db_path: &Path) -> Result<(), String> {
-  let conn = open_connection(db_path)?;
+fn normalize_stored_scheme_kinds(conn: &Connection) -> Result<usize, String> {
+  conn
+    .execute(
+      "UPDATE schemes SET kind = 'url' WHERE kind IS NULL OR kind NOT IN ('url', 'clipboard', 'shell')",
+      [],
+    )
+    .map_err(|err| format!("Failed to normalize scheme kinds: {err}"))
+}
 
+type Migration = fn(&Connection) -> Result<(), String>;
+
+/// Schema migrations in order: applying `MIGRATIONS[n]` takes `PRAGMA user_version`
+/// from `n` to `n + 1`. Append new entries; never edit or reorder shipped ones.
+const MIGRATIONS: &[Migration] = &[
+  migrate_baseline_schema,
+  migrate_task_notes,
+  migrate_task_color,
+  migrate_undo_log,
+  migrate_repeat_skip_weekends,
+];
+
+/// Version 1: the schema as it stood before versioning. Databases created by
+/// earlier builds report version 0 with any subset of these columns, so every
+/// step here must be idempotent.
+fn migrate_baseline_schema(conn: &Connection) -> Result<(), String> {
   conn
     .execute_batch(
       r#"
       CREATE TABLE IF NOT EXISTS lists (
         id TEXT PRIMARY KEY,
         name TEXT NOT NULL,
-        icon TEXT NOT NULL
+        icon TEXT NOT NULL,
+        color TEXT NULL,
+        position INTEGER NOT NULL DEFAULT 0,
+        default_reminder_offset INTEGER NULL
       );
 
       CREATE TABLE IF NOT EXISTS schemes (
@@ -370,7 +1555,9 @@ fn init_database(db_path: &Path) -> Result<(), String> {
         icon TEXT NOT NULL,
         template TEXT NOT NULL,
         kind TEXT NOT NULL,
-        param_type TEXT NOT NULL
+        param_type TEXT NOT NULL,
+        position INTEGER NOT NULL DEFAULT 0,
+        enabled INTEGER NOT NULL DEFAULT 1
       );
 
       CREATE TABLE IF NOT EXISTS tasks (
@@ -379,13 +1566,26 @@ fn init_database(db_path: &Path) -> Result<(), String> {
         title TEXT NOT NULL,
         detail TEXT NULL,
         completed INTEGER NOT NULL DEFAULT 0,
+        completed_at INTEGER NULL,
         date TEXT NULL,
         time TEXT NULL,
         reminder INTEGER NULL,
+        reminder_type TEXT NULL,
         reminder_offset_minutes INTEGER NULL,
+        reminder_depends_on TEXT NULL,
+        reminder_at TEXT NULL,
         repeat_type TEXT NULL,
         repeat_day_of_week TEXT NULL,
         repeat_day_of_month TEXT NULL,
+        repeat_week_of_month INTEGER NULL,
+        repeat_interval INTEGER NULL,
+        repeat_until TEXT NULL,
+        repeat_count INTEGER NULL,
+        repeat_on_short_month TEXT NULL,
+        priority INTEGER NOT NULL DEFAULT 1,
+        deleted_at INTEGER NULL,
+        sort_order INTEGER NOT NULL DEFAULT 0,
+        timezone TEXT NULL,
         created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
         updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
         FOREIGN KEY(list_id) REFERENCES lists(id) ON DELETE SET NULL
@@ -405,15 +1605,161 @@ fn init_database(db_path: &Path) -> Result<(), String> {
         task_id TEXT NOT NULL,
         remind_at INTEGER NOT NULL,
         fired_at INTEGER NOT NULL,
+        acknowledged_at INTEGER NULL,
         PRIMARY KEY(task_id, remind_at)
       );
+
+      CREATE TABLE IF NOT EXISTS task_changes (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        task_id TEXT NOT NULL,
+        change_type TEXT NOT NULL,
+        field TEXT NULL,
+        old_value TEXT NULL,
+        new_value TEXT NULL,
+        changed_at INTEGER NOT NULL,
+        FOREIGN KEY(task_id) REFERENCES tasks(id) ON DELETE CASCADE
+      );
+
+      CREATE INDEX IF NOT EXISTS idx_task_changes_task ON task_changes(task_id, id);
+
+      CREATE TABLE IF NOT EXISTS settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+      );
+
+      CREATE TABLE IF NOT EXISTS snoozed_reminders (
+        task_id TEXT NOT NULL,
+        original_remind_at INTEGER NOT NULL,
+        snoozed_until INTEGER NOT NULL,
+        created_at INTEGER NOT NULL,
+        PRIMARY KEY(task_id, original_remind_at),
+        FOREIGN KEY(task_id) REFERENCES tasks(id) ON DELETE CASCADE
+      );
+
+      CREATE TABLE IF NOT EXISTS tags (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE COLLATE NOCASE
+      );
+
+      CREATE TABLE IF NOT EXISTS task_tags (
+        task_id TEXT NOT NULL,
+        tag_id TEXT NOT NULL,
+        PRIMARY KEY(task_id, tag_id),
+        FOREIGN KEY(task_id) REFERENCES tasks(id) ON DELETE CASCADE,
+        FOREIGN KEY(tag_id) REFERENCES tags(id) ON DELETE CASCADE
+      );
+
+      CREATE TABLE IF NOT EXISTS task_templates (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        list_id TEXT NULL,
+        title TEXT NOT NULL,
+        detail TEXT NULL,
+        reminder TEXT NULL,
+        repeat TEXT NULL,
+        actions TEXT NOT NULL DEFAULT '[]',
+        created_at INTEGER NOT NULL
+      );
       "#,
     )
     .map_err(|err| format!("Failed to initialize schema: {err}"))?;
 
+  ensure_column(conn, "lists", "color", "TEXT NULL")?;
+  ensure_column(conn, "lists", "position", "INTEGER NOT NULL DEFAULT 0")?;
+  ensure_column(conn, "lists", "default_reminder_offset", "INTEGER NULL")?;
+  ensure_column(conn, "schemes", "enabled", "INTEGER NOT NULL DEFAULT 1")?;
+  ensure_column(conn, "schemes", "position", "INTEGER NOT NULL DEFAULT 0")?;
+  ensure_column(conn, "fired_reminders", "acknowledged_at", "INTEGER NULL")?;
+  ensure_column(conn, "tasks", "completed_at", "INTEGER NULL")?;
+  ensure_column(conn, "tasks", "reminder_type", "TEXT NULL")?;
+  ensure_column(conn, "tasks", "reminder_depends_on", "TEXT NULL")?;
+  ensure_column(conn, "tasks", "repeat_week_of_month", "INTEGER NULL")?;
+  ensure_column(conn, "tasks", "reminder_at", "TEXT NULL")?;
+  ensure_column(conn, "tasks", "repeat_interval", "INTEGER NULL")?;
+  ensure_column(conn, "tasks", "repeat_until", "TEXT NULL")?;
+  ensure_column(conn, "tasks", "repeat_count", "INTEGER NULL")?;
+  ensure_column(conn, "tasks", "repeat_on_short_month", "TEXT NULL")?;
+  ensure_column(conn, "tasks", "priority", "INTEGER NOT NULL DEFAULT 1")?;
+  ensure_column(conn, "tasks", "deleted_at", "INTEGER NULL")?;
+  ensure_column(conn, "tasks", "sort_order", "INTEGER NOT NULL DEFAULT 0")?;
+  ensure_column(conn, "tasks", "timezone", "TEXT NULL")?;
+
+  normalize_stored_scheme_kinds(conn)?;
+  Ok(())
+}
+
+/// Version 2: long-form task notes, kept out of the `tasks` row.
+fn migrate_task_notes(conn: &Connection) -> Result<(), String> {
+  conn
+    .execute_batch(
+      "CREATE TABLE task_notes (
+        task_id TEXT PRIMARY KEY,
+        body TEXT NOT NULL,
+        FOREIGN KEY(task_id) REFERENCES tasks(id) ON DELETE CASCADE
+      );",
+    )
+    .map_err(|err| format!("Failed to create task notes table: {err}"))
+}
+
+/// Version 3: per-task flag color.
+fn migrate_task_color(conn: &Connection) -> Result<(), String> {
+  conn
+    .execute_batch("ALTER TABLE tasks ADD COLUMN color TEXT NULL;")
+    .map_err(|err| format!("Failed to add tasks.color: {err}"))
+}
+
+/// Version 4: snapshots of deleted tasks and lists for `undo_last_delete`.
+fn migrate_undo_log(conn: &Connection) -> Result<(), String> {
+  conn
+    .execute_batch(
+      "CREATE TABLE undo_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        kind TEXT NOT NULL,
+        entity_id TEXT NOT NULL,
+        payload TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+      );",
+    )
+    .map_err(|err| format!("Failed to create undo log table: {err}"))
+}
+
+/// Version 5: weekday-only daily repeats.
+fn migrate_repeat_skip_weekends(conn: &Connection) -> Result<(), String> {
   conn
-    .execute("UPDATE schemes SET kind = 'url' WHERE kind IS NULL OR kind != 'url'", [])
-    .map_err(|err| format!("Failed to normalize scheme kinds: {err}"))?;
+    .execute_batch("ALTER TABLE tasks ADD COLUMN repeat_skip_weekends INTEGER NOT NULL DEFAULT 0;")
+    .map_err(|err| format!("Failed to add tasks.repeat_skip_weekends: {err}"))
+}
+
+/// Applies every migration newer than the database's `user_version`, each in its
+/// own transaction together with the version bump.
+fn run_migrations(conn: &mut Connection) -> Result<(), String> {
+  let current = usize::try_from(schema_user_version(conn)?).unwrap_or(0);
+  if current > MIGRATIONS.len() {
+    return Err(format!(
+      "Database schema version {current} is newer than this app supports ({})",
+      MIGRATIONS.len()
+    ));
+  }
+
+  for (index, migration) in MIGRATIONS.iter().enumerate().skip(current) {
+    let version = index + 1;
+    let tx = conn
+      .transaction()
+      .map_err(|err| format!("Failed to start migration transaction: {err}"))?;
+    migration(&tx).map_err(|err| format!("Migration to schema version {version} failed: {err}"))?;
+    tx
+      .pragma_update(None, "user_version", version as i64)
+      .map_err(|err| format!("Failed to record schema version: {err}"))?;
+    tx
+      .commit()
+      .map_err(|err| format!("Failed to commit migration: {err}"))?;
+  }
+  Ok(())
+}
+
+fn init_database(db_path: &Path) -> Result<(), String> {
+  let mut conn = open_connection(db_path)?;
+  run_migrations(&mut conn)?;
 
   let list_count: i64 = conn
     .query_row("SELECT COUNT(*) FROM lists", [], |row| row.get(0))
@@ -421,12 +1767,12 @@ fn init_database(db_path: &Path) -> Result<(), String> {
 
   if list_count == 0 {
     let mut stmt = conn
-      .prepare("INSERT INTO lists (id, name, icon) VALUES (?1, ?2, ?3)")
+      .prepare("INSERT INTO lists (id, name, icon, position) VALUES (?1, ?2, ?3, ?4)")
       .map_err(|err| format!("Failed to prepare list seed statement: {err}"))?;
 
-    for list in default_lists() {
+    for (position, list) in default_lists().into_iter().enumerate() {
       stmt
-        .execute(params![list.id, list.name, list.icon])
+        .execute(params![list.id, list.name, list.icon, position as i64])
         .map_err(|err| format!("Failed to seed lists: {err}"))?;
     }
   }
@@ -438,19 +1784,20 @@ fn init_database(db_path: &Path) -> Result<(), String> {
   if scheme_count == 0 {
     let mut stmt = conn
       .prepare(
-        "INSERT INTO schemes (id, name, icon, template, kind, param_type) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        "INSERT INTO schemes (id, name, icon, template, kind, param_type, position) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
       )
       .map_err(|err| format!("Failed to prepare scheme seed statement: {err}"))?;
 
-    for scheme in default_schemes() {
+    for (position, scheme) in default_schemes().into_iter().enumerate() {
       stmt
         .execute(params![
           scheme.id,
           scheme.name,
           scheme.icon,
           scheme.template,
-          scheme.kind,
-          scheme.param_type
+          scheme.kind.to_db(),
+          scheme.param_type,
+          position as i64
         ])
         .map_err(|err| format!("Failed to seed schemes: {err}"))?;
     }
@@ -459,19 +1806,25 @@ fn init_database(db_path: &Path) -> Result<(), String> {
   Ok(())
 }
 
+const LIST_COLUMNS: &str = "id, name, icon, color, default_reminder_offset";
+
+fn list_from_row(row: &rusqlite::Row) -> rusqlite::Result<ListItem> {
+  Ok(ListItem {
+    id: row.get(0)?,
+    name: row.get(1)?,
+    icon: row.get(2)?,
+    color: row.get(3)?,
+    default_reminder_offset: row.get(4)?,
+  })
+}
+
 fn load_lists(conn: &Connection) -> Result<Vec<ListItem>, String> {
   let mut stmt = conn
-    .prepare("SELECT id, name, icon FROM lists ORDER BY rowid ASC")
+    .prepare(&format!("SELECT {LIST_COLUMNS} FROM lists ORDER BY position ASC, rowid ASC"))
     .map_err(|err| format!("Failed to query lists: {err}"))?;
 
   let rows = stmt
-    .query_map([], |row| {
-      Ok(ListItem {
-        id: row.get(0)?,
-        name: row.get(1)?,
-        icon: row.get(2)?,
-      })
-    })
+    .query_map([], list_from_row)
     .map_err(|err| format!("Failed to map lists: {err}"))?;
 
   let mut lists = Vec::new();
@@ -481,22 +1834,43 @@ fn load_lists(conn: &Connection) -> Result<Vec<ListItem>, String> {
   Ok(lists)
 }
 
-fn load_schemes(conn: &Connection) -> Result<Vec<UrlScheme>, String> {
+fn fetch_list_by_id(conn: &Connection, list_id: &str) -> Result<ListItem, String> {
+  conn
+    .query_row(
+      &format!("SELECT {LIST_COLUMNS} FROM lists WHERE id = ?1"),
+      params![list_id],
+      list_from_row,
+    )
+    .map_err(|err| match err {
+      rusqlite::Error::QueryReturnedNoRows => "List not found".to_string(),
+      other => format!("Failed to load list: {other}"),
+    })
+}
+
+const SCHEME_COLUMNS: &str = "id, name, icon, template, kind, param_type, enabled";
+
+fn scheme_from_row(row: &rusqlite::Row) -> rusqlite::Result<UrlScheme> {
+  Ok(UrlScheme {
+    id: row.get(0)?,
+    name: row.get(1)?,
+    icon: row.get(2)?,
+    template: row.get(3)?,
+    kind: SchemeKind::from(row.get::<_, String>(4)?),
+    param_type: row.get(5)?,
+    enabled: row.get::<_, i64>(6)? != 0,
+  })
+}
+
+/// Loads schemes in display order. Pass `include_disabled = false` for pickers;
+/// anything that resolves existing bindings must include disabled schemes.
+fn load_schemes(conn: &Connection, include_disabled: bool) -> Result<Vec<UrlScheme>, String> {
+  let filter = if include_disabled { "" } else { "WHERE enabled = 1" };
   let mut stmt = conn
-    .prepare("SELECT id, name, icon, template, kind, param_type FROM schemes ORDER BY rowid ASC")
+    .prepare(&format!("SELECT {SCHEME_COLUMNS} FROM schemes {filter} ORDER BY position ASC, rowid ASC"))
     .map_err(|err| format!("Failed to query schemes: {err}"))?;
 
   let rows = stmt
-    .query_map([], |row| {
-      Ok(UrlScheme {
-        id: row.get(0)?,
-        name: row.get(1)?,
-        icon: row.get(2)?,
-        template: row.get(3)?,
-        kind: row.get(4)?,
-        param_type: row.get(5)?,
-      })
-    })
+    .query_map([], scheme_from_row)
     .map_err(|err| format!("Failed to map schemes: {err}"))?;
 
   let mut schemes = Vec::new();
@@ -506,13 +1880,37 @@ fn load_schemes(conn: &Connection) -> Result<Vec<UrlScheme>, String> {
   Ok(schemes)
 }
 
+fn fetch_scheme_by_id(conn: &Connection, scheme_id: &str) -> Result<UrlScheme, String> {
+  conn
+    .query_row(
+      &format!("SELECT {SCHEME_COLUMNS} FROM schemes WHERE id = ?1"),
+      params![scheme_id],
+      scheme_from_row,
+    )
+    .map_err(|err| match err {
+      rusqlite::Error::QueryReturnedNoRows => "Scheme not found".to_string(),
+      other => format!("Failed to load scheme: {other}"),
+    })
+}
+
 fn load_task_actions(conn: &Connection) -> Result<HashMap<String, Vec<TaskActionBinding>>, String> {
+  load_task_actions_where(conn, "", [])
+}
+
+/// Actions grouped by task, limited by a `WHERE` clause over `task_actions`.
+fn load_task_actions_where<P: rusqlite::Params>(
+  conn: &Connection,
+  clause: &str,
+  params: P,
+) -> Result<HashMap<String, Vec<TaskActionBinding>>, String> {
   let mut stmt = conn
-    .prepare("SELECT task_id, scheme_id, params FROM task_actions ORDER BY task_id ASC, position ASC")
+    .prepare(&format!(
+      "SELECT task_id, scheme_id, params FROM task_actions {clause} ORDER BY task_id ASC, position ASC"
+    ))
     .map_err(|err| format!("Failed to query task actions: {err}"))?;
 
   let rows = stmt
-    .query_map([], |row| {
+    .query_map(params, |row| {
       let task_id: String = row.get(0)?;
       let scheme_id: String = row.get(1)?;
       let params_json: String = row.get(2)?;
@@ -536,47 +1934,106 @@ fn load_task_actions(conn: &Connection) -> Result<HashMap<String, Vec<TaskAction
   Ok(grouped)
 }
 
-fn load_tasks(conn: &Connection) -> Result<Vec<TaskItem>, String> {
-  let action_map = load_task_actions(conn)?;
+const TASK_COLUMNS: &str = "t.id, t.list_id, t.title, t.detail, t.completed, t.completed_at, t.date, t.time, t.reminder, t.reminder_type, t.reminder_offset_minutes, t.reminder_depends_on, t.repeat_type, t.repeat_day_of_week, t.repeat_day_of_month, t.repeat_week_of_month, t.reminder_at, t.repeat_interval, t.repeat_until, t.repeat_count, t.repeat_on_short_month, t.priority, t.timezone, strftime('%Y-%m-%dT%H:%M:%SZ', t.created_at), strftime('%Y-%m-%dT%H:%M:%SZ', t.updated_at), t.color, t.repeat_skip_weekends";
+const TASK_COLUMN_COUNT: usize = 27;
+
+/// Maps a row selected with `TASK_COLUMNS` (aliasing `tasks` as `t`).
+fn task_from_row(
+  row: &rusqlite::Row,
+  action_map: &HashMap<String, Vec<TaskActionBinding>>,
+) -> rusqlite::Result<TaskItem> {
+  let id: String = row.get(0)?;
+  let repeat_type: Option<String> = row.get(12)?;
+  let repeat_day_of_week_json: Option<String> = row.get(13)?;
+  let repeat_day_of_month_json: Option<String> = row.get(14)?;
+  let repeat_week_of_month: Option<i64> = row.get(15)?;
+  let repeat_interval: Option<i64> = row.get(17)?;
+  let repeat_until: Option<String> = row.get(18)?;
+  let repeat_count: Option<i64> = row.get(19)?;
+  let repeat_on_short_month: Option<String> = row.get(20)?;
+  let repeat_skip_weekends: i64 = row.get(26)?;
+
+  let repeat_rule = repeat_type.map(|repeat_type_value| RepeatRule {
+    rule_type: repeat_type_value,
+    day_of_week: repeat_day_of_week_json
+      .as_deref()
+      .and_then(|text| serde_json::from_str::<Vec<u8>>(text).ok()),
+    day_of_month: repeat_day_of_month_json
+      .as_deref()
+      .and_then(|text| serde_json::from_str::<Vec<u8>>(text).ok()),
+    week_of_month: repeat_week_of_month.and_then(|value| i8::try_from(value).ok()),
+    interval: repeat_interval.and_then(|value| u32::try_from(value).ok()),
+    until: repeat_until,
+    count: repeat_count.and_then(|value| u32::try_from(value).ok()),
+    on_short_month: repeat_on_short_month,
+    skip_weekends: repeat_skip_weekends != 0,
+  });
+
+  Ok(TaskItem {
+    id: id.clone(),
+    list_id: row.get(1)?,
+    title: row.get(2)?,
+    detail: row.get(3)?,
+    completed: row.get::<_, i64>(4)? != 0,
+    completed_at: row.get(5)?,
+    due_date: row.get(6)?,
+    time: row.get(7)?,
+    reminder: reminder_from_db(row.get(8)?, row.get(9)?, row.get(10)?, row.get(11)?, row.get(16)?),
+    repeat_rule,
+    actions: action_map.get(&id).cloned(),
+    priority: Priority::from_db(row.get(21)?),
+    // Filled in by `attach_task_tags` where the caller needs them.
+    tags: Vec::new(),
+    timezone: row.get(22)?,
+    created_at: row.get(23)?,
+    updated_at: row.get(24)?,
+    color: row.get(25)?,
+  })
+}
 
+/// Tag names grouped by task, limited by a `WHERE` clause over `task_tags` (aliased `tt`).
+fn load_task_tags_where<P: rusqlite::Params>(
+  conn: &Connection,
+  clause: &str,
+  params: P,
+) -> Result<HashMap<String, Vec<String>>, String> {
   let mut stmt = conn
-    .prepare(
-      "SELECT id, list_id, title, detail, completed, date, time, reminder, reminder_offset_minutes, repeat_type, repeat_day_of_week, repeat_day_of_month
-       FROM tasks
-       ORDER BY completed ASC, date IS NULL ASC, date ASC, time IS NULL ASC, time ASC, rowid DESC",
-    )
-    .map_err(|err| format!("Failed to query tasks: {err}"))?;
+    .prepare(&format!(
+      "SELECT tt.task_id, g.name FROM task_tags tt JOIN tags g ON g.id = tt.tag_id {clause}
+       ORDER BY tt.task_id ASC, g.name COLLATE NOCASE ASC"
+    ))
+    .map_err(|err| format!("Failed to query task tags: {err}"))?;
 
   let rows = stmt
-    .query_map([], |row| {
-      let id: String = row.get(0)?;
-      let repeat_type: Option<String> = row.get(9)?;
-      let repeat_day_of_week_json: Option<String> = row.get(10)?;
-      let repeat_day_of_month_json: Option<String> = row.get(11)?;
-
-      let repeat_rule = repeat_type.map(|repeat_type_value| RepeatRule {
-        rule_type: repeat_type_value,
-        day_of_week: repeat_day_of_week_json
-          .as_deref()
-          .and_then(|text| serde_json::from_str::<Vec<u8>>(text).ok()),
-        day_of_month: repeat_day_of_month_json
-          .as_deref()
-          .and_then(|text| serde_json::from_str::<Vec<u8>>(text).ok()),
-      });
+    .query_map(params, |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+    .map_err(|err| format!("Failed to map task tags: {err}"))?;
 
-      Ok(TaskItem {
-        id: id.clone(),
-        list_id: row.get(1)?,
-        title: row.get(2)?,
-        detail: row.get(3)?,
-        completed: row.get::<_, i64>(4)? != 0,
-        due_date: row.get(5)?,
-        time: row.get(6)?,
-        reminder: reminder_from_db(row.get(7)?, row.get(8)?),
-        repeat_rule,
-        actions: action_map.get(&id).cloned(),
-      })
-    })
+  let mut grouped: HashMap<String, Vec<String>> = HashMap::new();
+  for row in rows {
+    let (task_id, tag) = row.map_err(|err| format!("Failed to read task tag row: {err}"))?;
+    grouped.entry(task_id).or_default().push(tag);
+  }
+  Ok(grouped)
+}
+
+fn attach_task_tags(tasks: &mut [TaskItem], mut tag_map: HashMap<String, Vec<String>>) {
+  for task in tasks {
+    task.tags = tag_map.remove(&task.id).unwrap_or_default();
+  }
+}
+
+fn query_tasks_with_actions<P: rusqlite::Params>(
+  conn: &Connection,
+  clause: &str,
+  params: P,
+  action_map: &HashMap<String, Vec<TaskActionBinding>>,
+) -> Result<Vec<TaskItem>, String> {
+  let mut stmt = conn
+    .prepare(&format!("SELECT {TASK_COLUMNS} FROM {LIVE_TASKS} t {clause}"))
+    .map_err(|err| format!("Failed to query tasks: {err}"))?;
+
+  let rows = stmt
+    .query_map(params, |row| task_from_row(row, action_map))
     .map_err(|err| format!("Failed to map tasks: {err}"))?;
 
   let mut tasks = Vec::new();
@@ -587,8 +2044,59 @@ fn load_tasks(conn: &Connection) -> Result<Vec<TaskItem>, String> {
   Ok(tasks)
 }
 
+/// Loads actions and tags for just these tasks, for queries run with an empty action map.
+fn attach_task_relations(conn: &Connection, tasks: &mut [TaskItem]) -> Result<(), String> {
+  if tasks.is_empty() {
+    return Ok(());
+  }
+
+  let ids: Vec<String> = tasks.iter().map(|task| task.id.clone()).collect();
+  let placeholders = sql_placeholders(ids.len());
+  let mut action_map = load_task_actions_where(
+    conn,
+    &format!("WHERE task_id IN ({placeholders})"),
+    params_from_iter(ids.iter()),
+  )?;
+  let tag_map = load_task_tags_where(
+    conn,
+    &format!("WHERE tt.task_id IN ({placeholders})"),
+    params_from_iter(ids.iter()),
+  )?;
+
+  for task in tasks.iter_mut() {
+    task.actions = action_map.remove(&task.id);
+  }
+  attach_task_tags(tasks, tag_map);
+  Ok(())
+}
+
+/// Runs `SELECT TASK_COLUMNS FROM <live tasks> t <clause>` and attaches each task's actions and tags.
+fn query_tasks<P: rusqlite::Params>(conn: &Connection, clause: &str, params: P) -> Result<Vec<TaskItem>, String> {
+  let action_map = load_task_actions(conn)?;
+  let mut tasks = query_tasks_with_actions(conn, clause, params, &action_map)?;
+  attach_task_tags(&mut tasks, load_task_tags_where(conn, "", [])?);
+  Ok(tasks)
+}
+
+/// Tasks not in the trash. Task queries select from this instead of `tasks` so soft-deleted
+/// rows stay invisible without every clause repeating the filter. `*` leaves out the
+/// implicit rowid, so it is selected by name for the `t.rowid` sort keys.
+const LIVE_TASKS: &str = "(SELECT rowid AS rowid, * FROM tasks WHERE deleted_at IS NULL)";
+
+const TASK_ORDER: &str = "ORDER BY t.completed ASC, CASE WHEN t.completed = 0 THEN t.priority ELSE 0 END DESC, t.date IS NULL ASC, t.date ASC, t.time IS NULL ASC, t.time ASC, t.rowid DESC";
+
+/// Matches tasks carrying the tag bound to the next `?`.
+const TAG_MATCH_CONDITION: &str = "EXISTS (
+  SELECT 1 FROM task_tags tt JOIN tags g ON g.id = tt.tag_id
+  WHERE tt.task_id = t.id AND g.name = ?
+)";
+
+fn load_tasks(conn: &Connection) -> Result<Vec<TaskItem>, String> {
+  query_tasks(conn, TASK_ORDER, [])
+}
+
 fn persist_task_actions(
-  tx: &rusqlite::Transaction,
+  tx: &Connection,
   task_id: &str,
   actions: &[TaskActionBinding],
 ) -> Result<(), String> {
@@ -613,14 +2121,331 @@ fn persist_task_actions(
   Ok(())
 }
 
-fn fetch_task_by_id(conn: &Connection, task_id: &str) -> Result<TaskItem, String> {
-  load_tasks(conn)?
+/// Trims tag names, drops blanks, and de-duplicates case-insensitively keeping the first spelling.
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+  let mut normalized: Vec<String> = Vec::new();
+  for tag in tags {
+    let tag = tag.trim();
+    if !tag.is_empty() && !normalized.iter().any(|existing| existing.eq_ignore_ascii_case(tag)) {
+      normalized.push(tag.to_string());
+    }
+  }
+  normalized
+}
+
+fn persist_task_tags(conn: &Connection, task_id: &str, tags: &[String]) -> Result<(), String> {
+  conn
+    .execute("DELETE FROM task_tags WHERE task_id = ?1", params![task_id])
+    .map_err(|err| format!("Failed to clear task tags: {err}"))?;
+
+  for tag in normalize_tags(tags) {
+    conn
+      .execute(
+        "INSERT OR IGNORE INTO tags (id, name) VALUES (?1, ?2)",
+        params![format!("tag_{}", Uuid::new_v4()), tag],
+      )
+      .map_err(|err| format!("Failed to create tag: {err}"))?;
+    conn
+      .execute(
+        "INSERT OR IGNORE INTO task_tags (task_id, tag_id) SELECT ?1, id FROM tags WHERE name = ?2",
+        params![task_id, tag],
+      )
+      .map_err(|err| format!("Failed to tag task: {err}"))?;
+  }
+  Ok(())
+}
+
+fn insert_task_row(conn: &Connection, task: &TaskItem) -> Result<(), String> {
+  let reminder = reminder_to_db(&task.reminder)?;
+  let repeat = repeat_to_db(&task.repeat_rule)?;
+
+  conn
+    .execute(
+      "INSERT INTO tasks (id, list_id, title, detail, completed, completed_at, date, time, reminder, reminder_type, reminder_offset_minutes, reminder_depends_on, repeat_type, repeat_day_of_week, repeat_day_of_month, repeat_week_of_month, reminder_at, repeat_interval, repeat_until, repeat_count, repeat_on_short_month, priority, timezone, sort_order, created_at, updated_at, color, repeat_skip_weekends)
+       VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23,
+               (SELECT COALESCE(MAX(sort_order), -1) + 1 FROM tasks WHERE list_id IS ?2),
+               COALESCE(strftime('%Y-%m-%d %H:%M:%S', ?24), CURRENT_TIMESTAMP),
+               COALESCE(strftime('%Y-%m-%d %H:%M:%S', ?25), CURRENT_TIMESTAMP),
+               ?26, ?27)",
+      params![
+        task.id,
+        task.list_id,
+        task.title,
+        task.detail,
+        if task.completed { 1 } else { 0 },
+        task.completed_at,
+        task.due_date,
+        task.time,
+        reminder.enabled,
+        reminder.reminder_type,
+        reminder.offset_minutes,
+        reminder.depends_on,
+        repeat.repeat_type,
+        repeat.day_of_week,
+        repeat.day_of_month,
+        repeat.week_of_month,
+        reminder.at,
+        repeat.interval,
+        repeat.until,
+        repeat.count,
+        repeat.on_short_month,
+        task.priority.to_db(),
+        task.timezone,
+        task.created_at,
+        task.updated_at,
+        task.color,
+        repeat.skip_weekends
+      ],
+    )
+    .map_err(|err| format!("Failed to insert task: {err}"))?;
+
+  if let Some(actions) = task.actions.as_ref() {
+    persist_task_actions(conn, &task.id, actions)?;
+  }
+  persist_task_tags(conn, &task.id, &task.tags)
+}
+
+/// Rewrites every editable column of an existing task and replaces its actions.
+/// Returns the number of task rows updated.
+fn update_task_row(conn: &Connection, task: &TaskItem) -> Result<usize, String> {
+  let reminder = reminder_to_db(&task.reminder)?;
+  let repeat = repeat_to_db(&task.repeat_rule)?;
+
+  let affected = conn
+    .execute(
+      "UPDATE tasks
+       SET list_id = ?2,
+           title = ?3,
+           detail = ?4,
+           completed = ?5,
+           completed_at = ?6,
+           date = ?7,
+           time = ?8,
+           reminder = ?9,
+           reminder_type = ?10,
+           reminder_offset_minutes = ?11,
+           reminder_depends_on = ?12,
+           repeat_type = ?13,
+           repeat_day_of_week = ?14,
+           repeat_day_of_month = ?15,
+           repeat_week_of_month = ?16,
+           reminder_at = ?17,
+           repeat_interval = ?18,
+           repeat_until = ?19,
+           repeat_count = ?20,
+           repeat_on_short_month = ?21,
+           priority = ?22,
+           timezone = ?23,
+           color = ?24,
+           repeat_skip_weekends = ?25,
+           updated_at = CURRENT_TIMESTAMP
+       WHERE id = ?1",
+      params![
+        task.id,
+        task.list_id,
+        task.title,
+        task.detail,
+        if task.completed { 1 } else { 0 },
+        task.completed_at,
+        task.due_date,
+        task.time,
+        reminder.enabled,
+        reminder.reminder_type,
+        reminder.offset_minutes,
+        reminder.depends_on,
+        repeat.repeat_type,
+        repeat.day_of_week,
+        repeat.day_of_month,
+        repeat.week_of_month,
+        reminder.at,
+        repeat.interval,
+        repeat.until,
+        repeat.count,
+        repeat.on_short_month,
+        task.priority.to_db(),
+        task.timezone,
+        task.color,
+        repeat.skip_weekends
+      ],
+    )
+    .map_err(|err| format!("Failed to update task: {err}"))?;
+
+  if affected > 0 {
+    persist_task_actions(conn, &task.id, task.actions.as_deref().unwrap_or_default())?;
+    persist_task_tags(conn, &task.id, &task.tags)?;
+  }
+  Ok(affected)
+}
+
+fn validate_reminder_dependency(conn: &Connection, task: &TaskItem) -> Result<(), String> {
+  let Some(depends_on) = task
+    .reminder
+    .as_ref()
+    .and_then(|reminder| reminder.depends_on_task_id.as_deref())
+  else {
+    return Ok(());
+  };
+  if depends_on == task.id {
+    return Err("A task's reminder cannot depend on itself".to_string());
+  }
+
+  let exists: i64 = conn
+    .query_row(
+      "SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1 AND deleted_at IS NULL)",
+      params![depends_on],
+      |row| row.get(0),
+    )
+    .map_err(|err| format!("Failed to look up reminder dependency: {err}"))?;
+  if exists == 0 {
+    return Err("Reminder dependency task not found".to_string());
+  }
+  Ok(())
+}
+
+fn record_task_change(
+  conn: &Connection,
+  task_id: &str,
+  change_type: &str,
+  field: Option<&str>,
+  old_value: Option<String>,
+  new_value: Option<String>,
+) -> Result<(), String> {
+  conn
+    .execute(
+      "INSERT INTO task_changes (task_id, change_type, field, old_value, new_value, changed_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+      params![task_id, change_type, field, old_value, new_value, now_epoch_ms()],
+    )
+    .map_err(|err| format!("Failed to record task change: {err}"))?;
+  Ok(())
+}
+
+fn encode_change_value<T: Serialize>(value: &Option<T>) -> Option<String> {
+  value.as_ref().and_then(|item| serde_json::to_string(item).ok())
+}
+
+fn task_field_values(task: &TaskItem) -> Vec<(&'static str, Option<String>)> {
+  vec![
+    ("listId", task.list_id.clone()),
+    ("title", Some(task.title.clone())),
+    ("detail", task.detail.clone()),
+    ("dueDate", task.due_date.clone()),
+    ("time", task.time.clone()),
+    ("reminder", encode_change_value(&task.reminder)),
+    ("repeat", encode_change_value(&task.repeat_rule)),
+    ("actions", encode_change_value(&task.actions)),
+    ("priority", encode_change_value(&Some(task.priority))),
+    ("tags", encode_change_value(&Some(&task.tags))),
+    ("timezone", task.timezone.clone()),
+    ("color", task.color.clone()),
+  ]
+}
+
+/// Records one "updated" row per edited field plus a completed/reopened row
+/// when the completion state flipped.
+fn record_task_edits(conn: &Connection, before: &TaskItem, after: &TaskItem) -> Result<(), String> {
+  for ((field, old_value), (_, new_value)) in task_field_values(before)
     .into_iter()
-    .find(|task| task.id == task_id)
-    .ok_or_else(|| "Task not found".to_string())
+    .zip(task_field_values(after))
+  {
+    if old_value != new_value {
+      record_task_change(conn, &after.id, "updated", Some(field), old_value, new_value)?;
+    }
+  }
+
+  if before.completed != after.completed {
+    let change_type = if after.completed { "completed" } else { "reopened" };
+    record_task_change(conn, &after.id, change_type, None, None, None)?;
+  }
+
+  Ok(())
+}
+
+fn load_task_changes(conn: &Connection, task_id: &str) -> Result<Vec<TaskChange>, String> {
+  let mut stmt = conn
+    .prepare(
+      "SELECT id, task_id, change_type, field, old_value, new_value, changed_at
+       FROM task_changes
+       WHERE task_id = ?1
+       ORDER BY id ASC",
+    )
+    .map_err(|err| format!("Failed to query task history: {err}"))?;
+
+  let rows = stmt
+    .query_map(params![task_id], |row| {
+      Ok(TaskChange {
+        id: row.get(0)?,
+        task_id: row.get(1)?,
+        change_type: row.get(2)?,
+        field: row.get(3)?,
+        old_value: row.get(4)?,
+        new_value: row.get(5)?,
+        changed_at: row.get(6)?,
+      })
+    })
+    .map_err(|err| format!("Failed to map task history: {err}"))?;
+
+  let mut changes = Vec::new();
+  for row in rows {
+    changes.push(row.map_err(|err| format!("Failed to read task history row: {err}"))?);
+  }
+  Ok(changes)
+}
+
+fn sql_placeholders(count: usize) -> String {
+  vec!["?"; count].join(", ")
+}
+
+fn fetch_task_by_id(conn: &Connection, task_id: &str) -> Result<TaskItem, String> {
+  let mut tasks = query_tasks_with_actions(conn, "WHERE t.id = ?1", params![task_id], &HashMap::new())?;
+  attach_task_relations(conn, &mut tasks)?;
+  tasks.pop().ok_or_else(|| "Task not found".to_string())
+}
+
+fn load_app_snapshot(conn: &Connection) -> Result<AppSnapshot, String> {
+  Ok(AppSnapshot {
+    lists: load_lists(conn)?,
+    tasks: load_tasks(conn)?,
+    schemes: load_schemes(conn, true)?,
+  })
+}
+
+fn load_task_notes(conn: &Connection) -> Result<Vec<TaskNote>, String> {
+  let mut stmt = conn
+    .prepare("SELECT task_id, body FROM task_notes ORDER BY task_id ASC")
+    .map_err(|err| format!("Failed to query task notes: {err}"))?;
+  let rows = stmt
+    .query_map([], |row| {
+      Ok(TaskNote {
+        task_id: row.get(0)?,
+        body: row.get(1)?,
+      })
+    })
+    .map_err(|err| format!("Failed to map task notes: {err}"))?;
+
+  let mut notes = Vec::new();
+  for row in rows {
+    notes.push(row.map_err(|err| format!("Failed to read task note row: {err}"))?);
+  }
+  Ok(notes)
+}
+
+/// Writes backed-up notes, skipping any whose task isn't in the database.
+fn restore_task_notes(conn: &Connection, notes: &[TaskNote]) -> Result<(), String> {
+  let mut stmt = conn
+    .prepare(
+      "INSERT OR REPLACE INTO task_notes (task_id, body)
+       SELECT ?1, ?2 WHERE EXISTS (SELECT 1 FROM tasks WHERE id = ?1)",
+    )
+    .map_err(|err| format!("Failed to prepare task note insert statement: {err}"))?;
+  for note in notes {
+    stmt
+      .execute(params![note.task_id, note.body])
+      .map_err(|err| format!("Failed to restore task note: {err}"))?;
+  }
+  Ok(())
 }
 
-fn persist_snapshot(conn: &mut Connection, snapshot: &AppSnapshot) -> Result<(), String> {
+fn persist_snapshot(conn: &mut Connection, snapshot: &AppSnapshot, notes: &[TaskNote]) -> Result<(), String> {
   let tx = conn
     .transaction()
     .map_err(|err| format!("Failed to start snapshot transaction: {err}"))?;
@@ -631,6 +2456,9 @@ fn persist_snapshot(conn: &mut Connection, snapshot: &AppSnapshot) -> Result<(),
   tx
     .execute("DELETE FROM fired_reminders", [])
     .map_err(|err| format!("Failed to clear fired reminders: {err}"))?;
+  tx
+    .execute("DELETE FROM undo_log", [])
+    .map_err(|err| format!("Failed to clear undo log: {err}"))?;
   tx
     .execute("DELETE FROM tasks", [])
     .map_err(|err| format!("Failed to clear tasks: {err}"))?;
@@ -640,14 +2468,26 @@ fn persist_snapshot(conn: &mut Connection, snapshot: &AppSnapshot) -> Result<(),
   tx
     .execute("DELETE FROM lists", [])
     .map_err(|err| format!("Failed to clear lists: {err}"))?;
+  tx
+    .execute("DELETE FROM tags", [])
+    .map_err(|err| format!("Failed to clear tags: {err}"))?;
 
   {
     let mut list_stmt = tx
-      .prepare("INSERT INTO lists (id, name, icon) VALUES (?1, ?2, ?3)")
+      .prepare(
+        "INSERT INTO lists (id, name, icon, color, position, default_reminder_offset) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+      )
       .map_err(|err| format!("Failed to prepare list insert statement: {err}"))?;
-    for list in &snapshot.lists {
+    for (position, list) in snapshot.lists.iter().enumerate() {
       list_stmt
-        .execute(params![list.id, list.name, list.icon])
+        .execute(params![
+          list.id,
+          list.name,
+          list.icon,
+          list.color,
+          position as i64,
+          list.default_reminder_offset
+        ])
         .map_err(|err| format!("Failed to insert list: {err}"))?;
     }
   }
@@ -655,77 +2495,146 @@ fn persist_snapshot(conn: &mut Connection, snapshot: &AppSnapshot) -> Result<(),
   {
     let mut scheme_stmt = tx
       .prepare(
-        "INSERT INTO schemes (id, name, icon, template, kind, param_type) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        "INSERT INTO schemes (id, name, icon, template, kind, param_type, enabled, position) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
       )
       .map_err(|err| format!("Failed to prepare scheme insert statement: {err}"))?;
-    for scheme in &snapshot.schemes {
+    for (position, scheme) in snapshot.schemes.iter().enumerate() {
       scheme_stmt
         .execute(params![
           scheme.id,
           scheme.name,
           scheme.icon,
           scheme.template,
-          scheme.kind,
-          scheme.param_type
+          scheme.kind.to_db(),
+          scheme.param_type,
+          scheme.enabled,
+          position as i64
         ])
         .map_err(|err| format!("Failed to insert scheme: {err}"))?;
     }
   }
 
-  {
-    let mut task_stmt = tx
-      .prepare(
-        "INSERT INTO tasks (id, list_id, title, detail, completed, date, time, reminder, reminder_offset_minutes, repeat_type, repeat_day_of_week, repeat_day_of_month)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
-      )
-      .map_err(|err| format!("Failed to prepare task insert statement: {err}"))?;
-
-    for task in &snapshot.tasks {
-      validate_repeat_rule(&task.repeat_rule)?;
-      let (reminder_enabled, reminder_offset_minutes) = reminder_to_db(&task.reminder)?;
-      let repeat_type = task.repeat_rule.as_ref().map(|rule| rule.rule_type.clone());
-      let repeat_day_of_week = task
-        .repeat_rule
-        .as_ref()
-        .and_then(|rule| rule.day_of_week.clone())
-        .map(|days| serde_json::to_string(&days))
-        .transpose()
-        .map_err(|err| format!("Failed to encode repeat days of week: {err}"))?;
-      let repeat_day_of_month = task
-        .repeat_rule
-        .as_ref()
-        .and_then(|rule| rule.day_of_month.clone())
-        .map(|days| serde_json::to_string(&days))
-        .transpose()
-        .map_err(|err| format!("Failed to encode repeat days of month: {err}"))?;
+  for task in &snapshot.tasks {
+    let task = TaskItem {
+      repeat_rule: normalize_repeat_rule(&task.repeat_rule)?,
+      ..task.clone()
+    };
+    insert_task_row(&tx, &task)?;
+  }
+  restore_task_notes(&tx, notes)?;
 
-      task_stmt
-        .execute(params![
-          task.id,
-          task.list_id,
-          task.title,
-          task.detail,
-          if task.completed { 1 } else { 0 },
-          task.due_date,
-          task.time,
-          reminder_enabled,
-          reminder_offset_minutes,
-          repeat_type,
-          repeat_day_of_week,
-          repeat_day_of_month
-        ])
-        .map_err(|err| format!("Failed to insert task: {err}"))?;
+  tx
+    .commit()
+    .map_err(|err| format!("Failed to commit snapshot transaction: {err}"))?;
+  Ok(())
+}
 
-      if let Some(actions) = task.actions.as_ref() {
-        persist_task_actions(&tx, &task.id, actions)?;
-      }
+fn row_exists(conn: &Connection, table: &str, id: &str) -> Result<bool, String> {
+  let exists: i64 = conn
+    .query_row(
+      &format!("SELECT EXISTS(SELECT 1 FROM {table} WHERE id = ?1)"),
+      params![id],
+      |row| row.get(0),
+    )
+    .map_err(|err| format!("Failed to look up {table} row: {err}"))?;
+  Ok(exists != 0)
+}
+
+/// Upserts the snapshot's lists, schemes and tasks by id. Existing lists and schemes
+/// keep their position; new ones are appended. Fired reminders are left alone.
+fn merge_snapshot(
+  conn: &mut Connection,
+  snapshot: &AppSnapshot,
+  notes: &[TaskNote],
+) -> Result<(MergeCounts, MergeCounts, MergeCounts), String> {
+  let tx = conn
+    .transaction()
+    .map_err(|err| format!("Failed to start merge transaction: {err}"))?;
+  let mut lists = MergeCounts::default();
+  let mut schemes = MergeCounts::default();
+  let mut tasks = MergeCounts::default();
+
+  for list in &snapshot.lists {
+    if row_exists(&tx, "lists", &list.id)? {
+      tx
+        .execute(
+          "UPDATE lists SET name = ?2, icon = ?3, color = ?4, default_reminder_offset = ?5 WHERE id = ?1",
+          params![list.id, list.name, list.icon, list.color, list.default_reminder_offset],
+        )
+        .map_err(|err| format!("Failed to update list: {err}"))?;
+      lists.updated += 1;
+    } else {
+      tx
+        .execute(
+          "INSERT INTO lists (id, name, icon, color, default_reminder_offset, position)
+           VALUES (?1, ?2, ?3, ?4, ?5, (SELECT COALESCE(MAX(position), -1) + 1 FROM lists))",
+          params![list.id, list.name, list.icon, list.color, list.default_reminder_offset],
+        )
+        .map_err(|err| format!("Failed to insert list: {err}"))?;
+      lists.added += 1;
+    }
+  }
+
+  for scheme in &snapshot.schemes {
+    if row_exists(&tx, "schemes", &scheme.id)? {
+      tx
+        .execute(
+          "UPDATE schemes SET name = ?2, icon = ?3, template = ?4, kind = ?5, param_type = ?6, enabled = ?7 WHERE id = ?1",
+          params![
+            scheme.id,
+            scheme.name,
+            scheme.icon,
+            scheme.template,
+            scheme.kind.to_db(),
+            scheme.param_type,
+            scheme.enabled
+          ],
+        )
+        .map_err(|err| format!("Failed to update scheme: {err}"))?;
+      schemes.updated += 1;
+    } else {
+      tx
+        .execute(
+          "INSERT INTO schemes (id, name, icon, template, kind, param_type, enabled, position)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, (SELECT COALESCE(MAX(position), -1) + 1 FROM schemes))",
+          params![
+            scheme.id,
+            scheme.name,
+            scheme.icon,
+            scheme.template,
+            scheme.kind.to_db(),
+            scheme.param_type,
+            scheme.enabled
+          ],
+        )
+        .map_err(|err| format!("Failed to insert scheme: {err}"))?;
+      schemes.added += 1;
+    }
+  }
+
+  for task in &snapshot.tasks {
+    let task = TaskItem {
+      repeat_rule: normalize_repeat_rule(&task.repeat_rule)?,
+      ..task.clone()
+    };
+    if row_exists(&tx, "tasks", &task.id)? {
+      update_task_row(&tx, &task)?;
+      // Backups only hold live tasks, so a trashed copy comes back out of the trash.
+      tx
+        .execute("UPDATE tasks SET deleted_at = NULL WHERE id = ?1", params![task.id])
+        .map_err(|err| format!("Failed to restore task: {err}"))?;
+      tasks.updated += 1;
+    } else {
+      insert_task_row(&tx, &task)?;
+      tasks.added += 1;
     }
   }
+  restore_task_notes(&tx, notes)?;
 
   tx
     .commit()
-    .map_err(|err| format!("Failed to commit snapshot transaction: {err}"))?;
-  Ok(())
+    .map_err(|err| format!("Failed to commit merge transaction: {err}"))?;
+  Ok((lists, schemes, tasks))
 }
 
 fn parse_date_ymd(value: &str) -> Option<NaiveDate> {
@@ -736,132 +2645,338 @@ fn parse_time_hm(value: &str) -> Option<NaiveTime> {
   NaiveTime::parse_from_str(value, "%H:%M").ok()
 }
 
-fn now_epoch_ms() -> i64 {
-  Utc::now().timestamp_millis()
+/// Rejects date and time strings the reminder math can't parse, so a malformed
+/// value fails at save time instead of silently never firing.
+fn validate_task_schedule(task: &TaskItem) -> Result<(), String> {
+  if let Some(due_date) = task.due_date.as_deref() {
+    if parse_date_ymd(due_date).is_none() {
+      return Err(format!("Invalid due date: {due_date} (expected YYYY-MM-DD)"));
+    }
+  }
+  if let Some(time) = task.time.as_deref() {
+    if parse_time_hm(time).is_none() {
+      return Err(format!("Invalid time: {time} (expected HH:MM)"));
+    }
+  }
+  let relative = task
+    .reminder
+    .as_ref()
+    .is_some_and(|reminder| reminder.reminder_type == "relative");
+  // A relative reminder counts back from the due instant, which needs both parts.
+  if relative && (task.due_date.is_none() || task.time.is_none()) {
+    return Err("A reminder requires both a date and a time".to_string());
+  }
+  Ok(())
 }
 
-fn compute_remind_at(task: &TaskItem) -> Option<i64> {
-  let due_date = parse_date_ymd(task.due_date.as_deref()?)?;
-  let due_time = parse_time_hm(task.time.as_deref()?)?;
-  let reminder = task.reminder.as_ref()?;
-  if reminder.reminder_type != "relative" {
-    return None;
-  }
+/// Also reads legacy forms like `9:30 AM`, `9:30pm`, `9 AM` and `09:30:00`.
+fn parse_time_flexible(value: &str) -> Option<NaiveTime> {
+  let value = value.trim();
+  ["%H:%M", "%H:%M:%S", "%I:%M %p", "%I:%M%p", "%I:%M:%S %p"]
+    .iter()
+    .find_map(|format| NaiveTime::parse_from_str(value, format).ok())
+    .or_else(|| {
+      // chrono needs minutes to parse a time, so hour-only forms get ":00" spliced in.
+      let split = value.find(|ch: char| !ch.is_ascii_digit())?;
+      let (hour, meridiem) = value.split_at(split);
+      NaiveTime::parse_from_str(&format!("{hour}:00{meridiem}"), "%I:%M%p")
+        .or_else(|_| NaiveTime::parse_from_str(&format!("{hour}:00{meridiem}"), "%I:%M %p"))
+        .ok()
+    })
+}
 
-  let naive_dt = due_date.and_time(due_time);
-  let due_local = match Local.from_local_datetime(&naive_dt) {
-    chrono::LocalResult::Single(dt) => dt,
-    chrono::LocalResult::Ambiguous(first, _) => first,
-    chrono::LocalResult::None => return None,
-  };
+/// Resolves a local wall-clock time to epoch ms, taking the earlier instant of a
+/// repeated time and rolling times skipped by DST forward.
+fn local_instant_ms(naive_dt: NaiveDateTime) -> Option<i64> {
+  zone_instant_ms(&Local, naive_dt, DstAmbiguity::Earliest)
+}
 
-  Some(due_local.timestamp_millis() - reminder.offset_minutes.max(0) * 60_000)
+fn zone_instant_ms<Z: TimeZone>(zone: &Z, naive_dt: NaiveDateTime, ambiguity: DstAmbiguity) -> Option<i64> {
+  match zone.from_local_datetime(&naive_dt) {
+    chrono::LocalResult::Single(dt) => Some(dt.timestamp_millis()),
+    chrono::LocalResult::Ambiguous(earliest, latest) => Some(match ambiguity {
+      DstAmbiguity::Earliest => earliest.timestamp_millis(),
+      DstAmbiguity::Latest => latest.timestamp_millis(),
+    }),
+    chrono::LocalResult::None => {
+      // A spring-forward gap: the time never shows on the clock, so take the first
+      // minute after it that does (02:30 becomes 03:00 when clocks jump 02:00 -> 03:00).
+      let mut probe = naive_dt.with_second(0)?.with_nanosecond(0)?;
+      for _ in 0..MAX_DST_GAP_MINUTES {
+        probe += Duration::minutes(1);
+        if let Some(dt) = zone.from_local_datetime(&probe).earliest() {
+          return Some(dt.timestamp_millis());
+        }
+      }
+      None
+    }
+  }
 }
 
-fn cleanup_old_fired_reminders(conn: &Connection, now_ms: i64) -> Result<(), String> {
-  let threshold = now_ms - FIRED_REMINDER_RETENTION_MS;
-  conn
-    .execute(
-      "DELETE FROM fired_reminders WHERE fired_at < ?1",
-      params![threshold],
-    )
-    .map_err(|err| format!("Failed to cleanup fired reminders: {err}"))?;
-  Ok(())
+/// Resolves wall-clock time in the task's own zone, or the system zone when it has none.
+fn task_instant_ms(task: &TaskItem, naive_dt: NaiveDateTime, ambiguity: DstAmbiguity) -> Option<i64> {
+  match task.timezone.as_deref().and_then(|name| name.parse::<Tz>().ok()) {
+    Some(zone) => zone_instant_ms(&zone, naive_dt, ambiguity),
+    None => zone_instant_ms(&Local, naive_dt, ambiguity),
+  }
 }
 
-fn is_reminder_fired(conn: &Connection, task_id: &str, remind_at_ms: i64) -> Result<bool, String> {
-  let exists: i64 = conn
-    .query_row(
-      "SELECT EXISTS(SELECT 1 FROM fired_reminders WHERE task_id = ?1 AND remind_at = ?2)",
-      params![task_id, remind_at_ms],
-      |row| row.get(0),
-    )
-    .map_err(|err| format!("Failed to check fired reminder: {err}"))?;
-  Ok(exists != 0)
+/// Canonical IANA name for `value`; blank means no zone.
+fn normalize_timezone(value: &str) -> Result<Option<String>, String> {
+  let value = value.trim();
+  if value.is_empty() {
+    return Ok(None);
+  }
+  value
+    .parse::<Tz>()
+    .map(|zone| Some(zone.name().to_string()))
+    .map_err(|_| format!("Unknown timezone: {value}"))
 }
 
-fn query_next_reminder(db_path: &Path, now_ms: i64) -> Result<Option<ReminderCandidate>, String> {
-  let conn = open_connection(db_path)?;
-  cleanup_old_fired_reminders(&conn, now_ms)?;
+/// When a task is due; date-only tasks count as due at the end of that day.
+fn task_due_instant(task: &TaskItem) -> Option<i64> {
+  let due_date = parse_date_ymd(task.due_date.as_deref()?)?;
+  let due_time = match task.time.as_deref() {
+    Some(time) => parse_time_hm(time)?,
+    None => NaiveTime::from_hms_opt(23, 59, 59)?,
+  };
+  task_instant_ms(task, due_date.and_time(due_time), DstAmbiguity::Earliest)
+}
+
+/// Accepts RFC 3339 with an offset, or a naive `YYYY-MM-DDTHH:MM[:SS]` in local time.
+fn parse_reminder_instant(value: &str) -> Option<i64> {
+  parse_naive_or_rfc3339(value, local_instant_ms)
+}
+
+fn parse_naive_or_rfc3339(value: &str, resolve: impl Fn(NaiveDateTime) -> Option<i64>) -> Option<i64> {
+  if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+    return Some(dt.timestamp_millis());
+  }
+  ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .and_then(resolve)
+}
+
+fn now_epoch_ms() -> i64 {
+  Utc::now().timestamp_millis()
+}
+
+/// `dependency_completed_at` is the completion time of the task an
+/// `after_completion_of` reminder waits on; it is ignored for relative reminders.
+fn compute_remind_at(
+  task: &TaskItem,
+  dependency_completed_at: Option<i64>,
+  ambiguity: DstAmbiguity,
+) -> Option<i64> {
+  let reminder = task.reminder.as_ref()?;
+  match reminder.reminder_type.as_str() {
+    "relative" => {
+      let due_date = parse_date_ymd(task.due_date.as_deref()?)?;
+      let due_time = parse_time_hm(task.time.as_deref()?)?;
+      let due_ms = task_instant_ms(task, due_date.and_time(due_time), ambiguity)?;
+
+      Some(due_ms - reminder.offset_minutes.max(0) * 60_000)
+    }
+    "absolute" => parse_naive_or_rfc3339(reminder.at.as_deref()?, |naive_dt| {
+      task_instant_ms(task, naive_dt, ambiguity)
+    }),
+    "after_completion_of" => Some(dependency_completed_at? + reminder.offset_minutes.max(0) * 60_000),
+    _ => None,
+  }
+}
+
+fn cleanup_old_fired_reminders(conn: &Connection, now_ms: i64) -> Result<(), String> {
+  let threshold = now_ms - fired_retention_ms(conn)?;
+  conn
+    .execute(
+      "DELETE FROM fired_reminders WHERE fired_at < ?1",
+      params![threshold],
+    )
+    .map_err(|err| format!("Failed to cleanup fired reminders: {err}"))?;
+  Ok(())
+}
+
+fn delete_fired_reminders_for_task(conn: &Connection, task_id: &str) -> Result<usize, String> {
+  conn
+    .execute("DELETE FROM fired_reminders WHERE task_id = ?1", params![task_id])
+    .map_err(|err| format!("Failed to reset fired reminders: {err}"))
+}
+
+/// Whether an edit moves the task's reminder, making any recorded `remind_at` stale.
+fn reminder_schedule_changed(before: &TaskItem, after: &TaskItem) -> bool {
+  before.due_date != after.due_date
+    || before.time != after.time
+    || before.timezone != after.timezone
+    || before.reminder != after.reminder
+}
+
+fn is_reminder_fired(conn: &Connection, task_id: &str, remind_at_ms: i64) -> Result<bool, String> {
+  let exists: i64 = conn
+    .query_row(
+      "SELECT EXISTS(SELECT 1 FROM fired_reminders WHERE task_id = ?1 AND remind_at = ?2)",
+      params![task_id, remind_at_ms],
+      |row| row.get(0),
+    )
+    .map_err(|err| format!("Failed to check fired reminder: {err}"))?;
+  Ok(exists != 0)
+}
 
+/// Snoozes fire at `snoozed_until`, which is recorded in `fired_reminders` under
+/// its own instant, so the original reminder's fired row never suppresses them.
+fn load_snooze_candidates(conn: &Connection) -> Result<Vec<ReminderCandidate>, String> {
   let mut stmt = conn
     .prepare(
-      "SELECT t.id, t.title, t.detail, t.date, t.time, t.reminder, t.reminder_offset_minutes, l.name
+      "SELECT s.task_id, t.title, t.detail, t.list_id, l.name, t.date, t.time, s.original_remind_at, s.snoozed_until
+       FROM snoozed_reminders s
+       JOIN tasks t ON t.id = s.task_id
+       LEFT JOIN lists l ON l.id = t.list_id
+       WHERE t.completed = 0 AND t.deleted_at IS NULL
+       ORDER BY s.snoozed_until ASC",
+    )
+    .map_err(|err| format!("Failed to query snoozed reminders: {err}"))?;
+
+  let rows = stmt
+    .query_map([], |row| {
+      Ok(ReminderCandidate {
+        task_id: row.get(0)?,
+        task_title: row.get(1)?,
+        task_detail: row.get(2)?,
+        list_id: row.get(3)?,
+        list_name: row.get(4)?,
+        due_date: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+        time: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+        snoozed_from: Some(row.get(7)?),
+        remind_at_ms: row.get(8)?,
+      })
+    })
+    .map_err(|err| format!("Failed to map snoozed reminders: {err}"))?;
+
+  let mut candidates = Vec::new();
+  for row in rows {
+    candidates.push(row.map_err(|err| format!("Failed to read snoozed reminder row: {err}"))?);
+  }
+  Ok(candidates)
+}
+
+fn delete_snooze(conn: &Connection, task_id: &str, original_remind_at: i64) -> Result<usize, String> {
+  conn
+    .execute(
+      "DELETE FROM snoozed_reminders WHERE task_id = ?1 AND original_remind_at = ?2",
+      params![task_id, original_remind_at],
+    )
+    .map_err(|err| format!("Failed to delete snooze: {err}"))
+}
+
+/// Every reminder (task-derived or snoozed) that is still due to fire as of
+/// `now_ms` and not older than the grace window. Ones already recorded as fired
+/// are left out unless `include_fired` is set.
+fn collect_reminder_candidates(
+  conn: &Connection,
+  now_ms: i64,
+  include_fired: bool,
+) -> Result<Vec<ReminderCandidate>, String> {
+  collect_reminder_candidates_since(conn, now_ms - reminder_grace_ms(conn)?, include_fired)
+}
+
+/// Like `collect_reminder_candidates`, but keeps everything at or after `earliest_ms`.
+fn collect_reminder_candidates_since(
+  conn: &Connection,
+  earliest_ms: i64,
+  include_fired: bool,
+) -> Result<Vec<ReminderCandidate>, String> {
+  let mut stmt = conn
+    .prepare(&format!(
+      "SELECT {TASK_COLUMNS}, l.name, d.completed_at
        FROM tasks t
        LEFT JOIN lists l ON l.id = t.list_id
+       LEFT JOIN tasks d ON d.id = t.reminder_depends_on
        WHERE t.completed = 0
-         AND t.date IS NOT NULL
-         AND t.time IS NOT NULL
          AND t.reminder = 1
-       ORDER BY t.date ASC, t.time ASC, t.rowid ASC",
-    )
+         AND t.deleted_at IS NULL
+       ORDER BY t.date ASC, t.time ASC, t.rowid ASC"
+    ))
     .map_err(|err| format!("Failed to query reminder candidates: {err}"))?;
 
+  let ambiguity = read_dst_ambiguity(conn)?;
+  let no_actions = HashMap::new();
   let rows = stmt
     .query_map([], |row| {
       Ok((
-        row.get::<_, String>(0)?,
-        row.get::<_, String>(1)?,
-        row.get::<_, Option<String>>(2)?,
-        row.get::<_, Option<String>>(3)?,
-        row.get::<_, Option<String>>(4)?,
-        row.get::<_, Option<i64>>(5)?,
-        row.get::<_, Option<i64>>(6)?,
-        row.get::<_, Option<String>>(7)?,
+        task_from_row(row, &no_actions)?,
+        row.get::<_, Option<String>>(TASK_COLUMN_COUNT)?,
+        row.get::<_, Option<i64>>(TASK_COLUMN_COUNT + 1)?,
       ))
     })
     .map_err(|err| format!("Failed to map reminder candidates: {err}"))?;
 
-  let mut next: Option<ReminderCandidate> = None;
+  let mut candidates = Vec::new();
   for row in rows {
-    let (task_id, title, detail, due_date, time, reminder_enabled, reminder_offset, list_name) =
+    let (task, list_name, dependency_completed_at) =
       row.map_err(|err| format!("Failed to read reminder candidate row: {err}"))?;
-    if reminder_enabled.unwrap_or(0) == 0 {
-      continue;
-    }
 
-    let task = TaskItem {
-      id: task_id.clone(),
-      list_id: None,
-      title: title.clone(),
-      detail: detail.clone(),
-      completed: false,
-      due_date: due_date.clone(),
-      time: time.clone(),
-      reminder: reminder_from_db(reminder_enabled, reminder_offset),
-      repeat_rule: None,
-      actions: None,
-    };
-    let Some(remind_at_ms) = compute_remind_at(&task) else {
+    let Some(remind_at_ms) = compute_remind_at(&task, dependency_completed_at, ambiguity) else {
       continue;
     };
-    if remind_at_ms < now_ms - REMINDER_GRACE_MS {
+    candidates.push(ReminderCandidate {
+      task_id: task.id,
+      task_title: task.title,
+      task_detail: task.detail,
+      list_id: task.list_id,
+      list_name,
+      due_date: task.due_date.unwrap_or_default(),
+      time: task.time.unwrap_or_default(),
+      remind_at_ms,
+      snoozed_from: None,
+    });
+  }
+  candidates.extend(load_snooze_candidates(conn)?);
+
+  let mut pending = Vec::new();
+  for candidate in candidates {
+    if candidate.remind_at_ms < earliest_ms {
       continue;
     }
-    if is_reminder_fired(&conn, &task_id, remind_at_ms)? {
+    if !include_fired && is_reminder_fired(conn, &candidate.task_id, candidate.remind_at_ms)? {
       continue;
     }
+    pending.push(candidate);
+  }
+  Ok(pending)
+}
 
-    let candidate = ReminderCandidate {
-      task_id,
-      task_title: title,
-      task_detail: detail,
-      list_name,
-      due_date: due_date.unwrap_or_default(),
-      time: time.unwrap_or_default(),
-      remind_at_ms,
-    };
+fn query_next_reminder(db_path: &Path, now_ms: i64) -> Result<Option<ReminderCandidate>, String> {
+  Ok(query_next_reminders(db_path, now_ms)?.into_iter().next())
+}
 
-    let should_replace = next
-      .as_ref()
-      .map(|existing| candidate.remind_at_ms < existing.remind_at_ms)
-      .unwrap_or(true);
-    if should_replace {
-      next = Some(candidate);
-    }
-  }
+/// Every pending reminder sharing the earliest `remind_at`, in candidate order,
+/// so the scheduler can fire simultaneous reminders as one batch.
+fn query_next_reminders(db_path: &Path, now_ms: i64) -> Result<Vec<ReminderCandidate>, String> {
+  let conn = open_connection(db_path)?;
+  cleanup_old_fired_reminders(&conn, now_ms)?;
+
+  let candidates = collect_reminder_candidates(&conn, now_ms, false)?;
+  let Some(earliest_ms) = candidates.iter().map(|candidate| candidate.remind_at_ms).min() else {
+    return Ok(Vec::new());
+  };
+  Ok(
+    candidates
+      .into_iter()
+      .filter(|candidate| candidate.remind_at_ms == earliest_ms)
+      .collect(),
+  )
+}
 
-  Ok(next)
+fn debug_reminder(candidate: ReminderCandidate, now: i64) -> DebugNextReminder {
+  DebugNextReminder {
+    task_id: candidate.task_id,
+    task_title: candidate.task_title,
+    remind_at: candidate.remind_at_ms,
+    due_date: candidate.due_date,
+    time: candidate.time,
+    now,
+    delay_ms: candidate.remind_at_ms.saturating_sub(now),
+  }
 }
 
 fn mark_reminder_fired(
@@ -879,6 +2994,34 @@ fn mark_reminder_fired(
   Ok(affected == 1)
 }
 
+/// Web-style labels: "granted", "denied", or "default" while the user hasn't been asked.
+fn permission_label(state: tauri::plugin::PermissionState) -> &'static str {
+  match state {
+    tauri::plugin::PermissionState::Granted => "granted",
+    tauri::plugin::PermissionState::Denied => "denied",
+    _ => "default",
+  }
+}
+
+/// Logs a failed `.show()`, telling a missing permission apart from other errors and
+/// letting the webview know in the former case so it can prompt the user.
+fn report_notification_error(app: &AppHandle, context: &str, error: &str) {
+  let state = app.notification().permission_state();
+  match state {
+    Ok(tauri::plugin::PermissionState::Granted) => eprintln!("{context} notification error: {error}"),
+    Ok(state) => {
+      let label = permission_label(state);
+      eprintln!("{context} notification blocked, permission {label}: {error}");
+      if let Err(emit_error) = app.emit(NOTIFICATION_BLOCKED_EVENT, label) {
+        eprintln!("{context} emit notification blocked error: {emit_error}");
+      }
+    }
+    Err(state_error) => {
+      eprintln!("{context} notification error: {error} (permission state unavailable: {state_error})")
+    }
+  }
+}
+
 fn send_task_reminder_notification(app: &AppHandle, candidate: &ReminderCandidate) -> Result<(), String> {
   let body = candidate
     .task_detail
@@ -891,7 +3034,8 @@ fn send_task_reminder_notification(app: &AppHandle, candidate: &ReminderCandidat
         .as_ref()
         .map(|name| format!("{} · ", name))
         .unwrap_or_default();
-      format!("{list_prefix}{} {}", candidate.due_date, candidate.time)
+      let schedule = format!("{} {}", candidate.due_date, candidate.time);
+      format!("{list_prefix}{}", schedule.trim())
     });
 
   app
@@ -899,36 +3043,201 @@ fn send_task_reminder_notification(app: &AppHandle, candidate: &ReminderCandidat
     .builder()
     .title(format!("任务提醒：{}", candidate.task_title))
     .body(body)
+    .action_type_id(REMINDER_ACTION_TYPE)
+    .extra("taskId", &candidate.task_id)
+    .extra("remindAt", candidate.snoozed_from.unwrap_or(candidate.remind_at_ms))
+    .show()
+    .map_err(|err| format!("Failed to show notification: {err}"))
+}
+
+/// One title per line, capped so a large batch doesn't produce a wall of text.
+fn reminder_titles_body(candidates: &[ReminderCandidate]) -> String {
+  const LISTED: usize = 5;
+  let mut body = candidates
+    .iter()
+    .take(LISTED)
+    .map(|candidate| candidate.task_title.as_str())
+    .collect::<Vec<_>>()
+    .join("\n");
+  if candidates.len() > LISTED {
+    body.push_str(&format!("\n…以及另外 {} 项", candidates.len() - LISTED));
+  }
+  body
+}
+
+/// Single notification for several reminders that came due at the same instant.
+/// Carries the same Complete/Snooze buttons as a single reminder; the `reminders`
+/// extra lists every task in the batch so a button applies to all of them.
+fn send_grouped_reminders_notification(app: &AppHandle, due: &[ReminderCandidate]) -> Result<(), String> {
+  let targets: Vec<ReminderActionTarget> = due
+    .iter()
+    .map(|candidate| ReminderActionTarget {
+      task_id: candidate.task_id.clone(),
+      remind_at: candidate.snoozed_from.unwrap_or(candidate.remind_at_ms),
+    })
+    .collect();
+  app
+    .notification()
+    .builder()
+    .title(format!("{} 个任务到期", due.len()))
+    .body(reminder_titles_body(due))
+    .action_type_id(REMINDER_ACTION_TYPE)
+    .extra("reminders", targets)
+    .show()
+    .map_err(|err| format!("Failed to show notification: {err}"))
+}
+
+fn send_missed_reminders_notification(app: &AppHandle, missed: &[ReminderCandidate]) -> Result<(), String> {
+  app
+    .notification()
+    .builder()
+    .title(format!("错过了 {} 个任务提醒", missed.len()))
+    .body(reminder_titles_body(missed))
     .show()
     .map_err(|err| format!("Failed to show notification: {err}"))
 }
 
+fn write_snapshot_cache(cache_path: &Path, snapshot: &AppSnapshot) -> Result<(), String> {
+  let cache = SnapshotCache {
+    cache_version: SNAPSHOT_CACHE_VERSION,
+    app_version: env!("CARGO_PKG_VERSION").to_string(),
+    written_at: now_epoch_ms(),
+    snapshot: snapshot.clone(),
+  };
+  let content =
+    serde_json::to_vec(&cache).map_err(|err| format!("Failed to encode snapshot cache: {err}"))?;
+
+  // Write to a sibling file first so a crash never leaves a half-written cache behind.
+  let tmp_path = cache_path.with_extension("json.tmp");
+  fs::write(&tmp_path, content).map_err(|err| format!("Failed to write snapshot cache: {err}"))?;
+  fs::rename(&tmp_path, cache_path).map_err(|err| format!("Failed to replace snapshot cache: {err}"))?;
+  Ok(())
+}
+
+/// Returns `None` when the cache is missing, unreadable, or was written by a
+/// different cache format or app version.
+fn read_snapshot_cache(cache_path: &Path) -> Option<AppSnapshot> {
+  let content = fs::read(cache_path).ok()?;
+  let cache: SnapshotCache = serde_json::from_slice(&content).ok()?;
+  if cache.cache_version != SNAPSHOT_CACHE_VERSION || cache.app_version != env!("CARGO_PKG_VERSION") {
+    return None;
+  }
+  Some(cache.snapshot)
+}
+
+fn mark_snapshot_dirty(db: &DbState) {
+  db.cache_dirty.notify_one();
+}
+
+async fn snapshot_cache_loop(db_path: PathBuf, cache_path: PathBuf, dirty: Arc<Notify>) {
+  loop {
+    dirty.notified().await;
+
+    // Debounce bursts of mutations into a single write.
+    loop {
+      tokio::select! {
+        _ = dirty.notified() => continue,
+        _ = sleep(TokioDuration::from_millis(SNAPSHOT_CACHE_DEBOUNCE_MS)) => break,
+      }
+    }
+
+    let result = open_connection(&db_path)
+      .and_then(|conn| load_app_snapshot(&conn))
+      .and_then(|snapshot| write_snapshot_cache(&cache_path, &snapshot));
+    if let Err(error) = result {
+      eprintln!("snapshot cache write error: {error}");
+    }
+  }
+}
+
 fn scheduler_wakeup(scheduler: &SchedulerState) {
   scheduler.wakeup.notify_one();
 }
 
+/// Startup pass for reminders that came due while the app was closed: everything
+/// unfired between the catch-up window and the grace window is marked fired and
+/// summarized in one notification. Newer ones are left for the scheduler to fire
+/// individually as usual.
+fn catch_up_missed_reminders(app: &AppHandle, db_path: &Path) -> Result<usize, String> {
+  let conn = open_connection(db_path)?;
+  let window_minutes = read_u32_setting(&conn, SETTING_MISSED_REMINDER_WINDOW)?;
+  if window_minutes == 0 {
+    return Ok(0);
+  }
+
+  let now_ms = now_epoch_ms();
+  let earliest_ms = now_ms - i64::from(window_minutes) * 60 * 1000;
+  let grace_ms = reminder_grace_ms(&conn)?;
+  let mut missed: Vec<ReminderCandidate> = collect_reminder_candidates_since(&conn, earliest_ms, false)?
+    .into_iter()
+    .filter(|candidate| candidate.remind_at_ms < now_ms - grace_ms)
+    .collect();
+  missed.sort_by_key(|candidate| candidate.remind_at_ms);
+  missed.retain(|candidate| {
+    mark_reminder_fired(&conn, &candidate.task_id, candidate.remind_at_ms, now_ms).unwrap_or_else(|error| {
+      eprintln!("missed reminder mark fired error: {error}");
+      false
+    })
+  });
+  for candidate in &missed {
+    if let Some(original_remind_at) = candidate.snoozed_from {
+      delete_snooze(&conn, &candidate.task_id, original_remind_at)?;
+    }
+  }
+
+  if !missed.is_empty() {
+    if let Err(error) = send_missed_reminders_notification(app, &missed) {
+      report_notification_error(app, "missed reminder", &error);
+    }
+  }
+  Ok(missed.len())
+}
+
+/// Keeps only the candidates that would still be picked now: the task exists, is
+/// incomplete, still has a reminder, and that reminder still falls at the same time.
+fn revalidate_reminder_candidates(
+  conn: &Connection,
+  due: Vec<ReminderCandidate>,
+  now_ms: i64,
+) -> Result<Vec<ReminderCandidate>, String> {
+  let current: HashSet<(String, i64)> = collect_reminder_candidates(conn, now_ms, true)?
+    .into_iter()
+    .map(|candidate| (candidate.task_id, candidate.remind_at_ms))
+    .collect();
+  Ok(
+    due
+      .into_iter()
+      .filter(|candidate| current.contains(&(candidate.task_id.clone(), candidate.remind_at_ms)))
+      .collect(),
+  )
+}
+
 async fn scheduler_loop(app: AppHandle, db_path: PathBuf, wakeup: Arc<Notify>) {
+  if let Err(error) = catch_up_missed_reminders(&app, &db_path) {
+    eprintln!("scheduler missed reminder catch-up error: {error}");
+  }
+
   loop {
     let now_ms = now_epoch_ms();
-    let next = match query_next_reminder(&db_path, now_ms) {
-      Ok(next) => next,
+    let due = match query_next_reminders(&db_path, now_ms) {
+      Ok(due) => due,
       Err(error) => {
-        eprintln!("scheduler query_next_reminder error: {error}");
+        eprintln!("scheduler query_next_reminders error: {error}");
         tokio::select! {
           _ = wakeup.notified() => {},
-          _ = sleep(TokioDuration::from_secs(5)) => {},
+          _ = sleep(TokioDuration::from_millis(SCHEDULER_RETRY_MS)) => {},
         }
         continue;
       }
     };
 
-    let Some(candidate) = next else {
+    let Some(remind_at_ms) = due.first().map(|candidate| candidate.remind_at_ms) else {
       wakeup.notified().await;
       continue;
     };
 
     let now_ms = now_epoch_ms();
-    let delay_ms = candidate.remind_at_ms.saturating_sub(now_ms);
+    let delay_ms = remind_at_ms.saturating_sub(now_ms);
 
     if delay_ms > 0 {
       tokio::select! {
@@ -952,49 +3261,155 @@ async fn scheduler_loop(app: AppHandle, db_path: PathBuf, wakeup: Arc<Notify>) {
       eprintln!("scheduler cleanup fired reminders error: {error}");
     }
 
-    match mark_reminder_fired(&conn, &candidate.task_id, candidate.remind_at_ms, fired_at_ms) {
-      Ok(true) => {
-        if let Err(error) = send_task_reminder_notification(&app, &candidate) {
-          eprintln!("scheduler send notification error: {error}");
+    // The task may have been deleted, completed or rescheduled while we slept.
+    let due = match revalidate_reminder_candidates(&conn, due, fired_at_ms) {
+      Ok(due) => due,
+      Err(error) => {
+        eprintln!("scheduler revalidate reminders error: {error}");
+        continue;
+      }
+    };
+
+    let mut fired = Vec::new();
+    for candidate in due {
+      match mark_reminder_fired(&conn, &candidate.task_id, candidate.remind_at_ms, fired_at_ms) {
+        Ok(true) => {
+          let payload = ReminderFiredEvent {
+            task_id: candidate.task_id.clone(),
+            remind_at: candidate.remind_at_ms,
+            action_remind_at: candidate.snoozed_from.unwrap_or(candidate.remind_at_ms),
+          };
+          if let Err(error) = app.emit(REMINDER_FIRED_EVENT, payload) {
+            eprintln!("scheduler emit reminder fired error: {error}");
+          }
+          fired.push(candidate);
+        }
+        Ok(false) => {}
+        Err(error) => eprintln!("scheduler mark reminder fired error: {error}"),
+      }
+    }
+
+    let sent = match fired.as_slice() {
+      [] => Ok(()),
+      [candidate] => send_task_reminder_notification(&app, candidate),
+      batch => send_grouped_reminders_notification(&app, batch),
+    };
+    if let Err(error) = sent {
+      report_notification_error(&app, "scheduler", &error);
+    }
+
+    for candidate in &fired {
+      if let Some(original_remind_at) = candidate.snoozed_from {
+        if let Err(error) = delete_snooze(&conn, &candidate.task_id, original_remind_at) {
+          eprintln!("scheduler clear snooze error: {error}");
         }
       }
-      Ok(false) => {}
-      Err(error) => eprintln!("scheduler mark reminder fired error: {error}"),
     }
   }
 }
 
+fn last_day_of_month(year: i32, month: u32) -> Option<NaiveDate> {
+  let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+  NaiveDate::from_ymd_opt(next_year, next_month, 1)?.pred_opt()
+}
+
+/// The `ordinal`-th `weekday` (0 = Sunday) of a month, where -1 means the last
+/// one. Returns `None` when the month has no such occurrence (e.g. a 5th Monday).
+fn nth_weekday_of_month(year: i32, month: u32, weekday: u8, ordinal: i8) -> Option<NaiveDate> {
+  if ordinal == -1 {
+    let last = last_day_of_month(year, month)?;
+    let back = (last.weekday().num_days_from_sunday() as i64 - weekday as i64).rem_euclid(7);
+    return last.checked_sub_signed(Duration::days(back));
+  }
+
+  let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+  let forward = (weekday as i64 - first.weekday().num_days_from_sunday() as i64).rem_euclid(7);
+  let date = first.checked_add_signed(Duration::days(forward + 7 * (ordinal as i64 - 1)))?;
+  if date.month() == month { Some(date) } else { None }
+}
+
+fn add_months(year: i32, month: u32, step: u32) -> (i32, u32) {
+  let zero_based = month - 1 + step;
+  (year + (zero_based / 12) as i32, zero_based % 12 + 1)
+}
+
+/// Next "nth weekday" occurrence strictly after `current_date`; months lacking
+/// the requested occurrence are skipped.
+fn next_nth_weekday_date(
+  current_date: NaiveDate,
+  ordinal: i8,
+  weekdays: &[u8],
+  month_step: u32,
+) -> Option<NaiveDate> {
+  let mut year = current_date.year();
+  let mut month = current_date.month();
+  for _ in 0..=24 {
+    let next = weekdays
+      .iter()
+      .filter_map(|weekday| nth_weekday_of_month(year, month, *weekday, ordinal))
+      .filter(|date| *date > current_date)
+      .min();
+    if next.is_some() {
+      return next;
+    }
+
+    (year, month) = add_months(year, month, month_step);
+  }
+  None
+}
+
+/// First date strictly after `current` whose weekday (0 = Sunday) is in `days`; a rule
+/// containing only the current weekday lands a full week later.
+fn next_weekly_date(current: NaiveDate, days: &[u8]) -> Option<NaiveDate> {
+  let today_weekday = current.weekday().num_days_from_sunday() as u8;
+  (1..=7u8)
+    .find(|offset| days.contains(&((today_weekday + offset) % 7)))
+    .and_then(|offset| current.checked_add_signed(Duration::days(offset as i64)))
+}
+
+/// The next occurrence's date, or `None` once the rule's count or end date is used up.
 fn compute_next_repeat_date(task: &TaskItem) -> Option<String> {
+  let repeat_rule = task.repeat_rule.as_ref()?;
+  if repeat_rule.count.is_some_and(|count| count <= 1) {
+    return None;
+  }
+  let next = next_repeat_date_unbounded(task)?;
+  if let Some(until) = repeat_rule.until.as_deref().and_then(parse_date_ymd) {
+    if parse_date_ymd(&next)? > until {
+      return None;
+    }
+  }
+  Some(next)
+}
+
+fn next_repeat_date_unbounded(task: &TaskItem) -> Option<String> {
   let repeat_rule = task.repeat_rule.as_ref()?;
   let current_date = parse_date_ymd(task.due_date.as_deref()?)?;
+  let interval = repeat_rule.interval.unwrap_or(1).max(1);
 
   let next = match repeat_rule.rule_type.as_str() {
-    "daily" => current_date.checked_add_signed(Duration::days(1))?,
-    "weekly" => {
-      let mut days = repeat_rule.day_of_week.clone().unwrap_or_default();
-      if days.is_empty() {
-        return None;
+    "daily" => {
+      let next = current_date.checked_add_signed(Duration::days(interval as i64))?;
+      match next.weekday() {
+        Weekday::Sat if repeat_rule.skip_weekends => next.checked_add_signed(Duration::days(2))?,
+        Weekday::Sun if repeat_rule.skip_weekends => next.checked_add_signed(Duration::days(1))?,
+        _ => next,
       }
-      days.sort_unstable();
-      let today_weekday = current_date.weekday().num_days_from_sunday() as u8;
-
-      let mut target_offset: Option<i64> = None;
-      for day in days {
-        if day > today_weekday {
-          target_offset = Some((day - today_weekday) as i64);
-          break;
-        }
+    }
+    "weekly" => {
+      let next = next_weekly_date(current_date, repeat_rule.day_of_week.as_deref().unwrap_or_default())?;
+      // Later weekdays in the same (Sunday-based) week follow directly; wrapping into a new
+      // week skips the interval's idle weeks.
+      let week_start = |date: NaiveDate| date - Duration::days(date.weekday().num_days_from_sunday() as i64);
+      if week_start(next) == week_start(current_date) {
+        next
+      } else {
+        next.checked_add_signed(Duration::weeks(interval as i64 - 1))?
       }
-      let fallback = repeat_rule
-        .day_of_week
-        .as_ref()
-        .and_then(|items| items.iter().min().copied())
-        .map(|day| {
-          let delta = (7 - today_weekday as i64) + day as i64;
-          if delta <= 0 { 7 } else { delta }
-        })?;
-
-      current_date.checked_add_signed(Duration::days(target_offset.unwrap_or(fallback)))?
+    }
+    "monthly" if repeat_rule.week_of_month.is_some() => {
+      let weekdays = repeat_rule.day_of_week.clone().unwrap_or_default();
+      next_nth_weekday_date(current_date, repeat_rule.week_of_month?, &weekdays, interval)?
     }
     "monthly" => {
       let mut days = repeat_rule.day_of_month.clone().unwrap_or_default();
@@ -1002,32 +3417,25 @@ fn compute_next_repeat_date(task: &TaskItem) -> Option<String> {
         return None;
       }
       days.sort_unstable();
-      let current_day = current_date.day() as u8;
-
-      for day in &days {
-        if *day > current_day {
-          if let Some(candidate) =
-            NaiveDate::from_ymd_opt(current_date.year(), current_date.month(), *day as u32)
-          {
-            return Some(candidate.format("%Y-%m-%d").to_string());
-          }
-        }
-      }
+      let clamp_to_last_day = repeat_rule.on_short_month.as_deref() == Some("last_day");
+      let day_in_month = |year: i32, month: u32, day: u8| {
+        NaiveDate::from_ymd_opt(year, month, day as u32)
+          .or_else(|| clamp_to_last_day.then(|| last_day_of_month(year, month)).flatten())
+      };
 
       let mut year = current_date.year();
       let mut month = current_date.month();
-      for _ in 0..24 {
-        if month == 12 {
-          month = 1;
-          year += 1;
-        } else {
-          month += 1;
+      for step in 0..=24 {
+        if step > 0 {
+          (year, month) = add_months(year, month, interval);
         }
 
-        for day in &days {
-          if let Some(candidate) = NaiveDate::from_ymd_opt(year, month, *day as u32) {
-            return Some(candidate.format("%Y-%m-%d").to_string());
-          }
+        let next = days
+          .iter()
+          .filter_map(|day| day_in_month(year, month, *day))
+          .find(|candidate| *candidate > current_date);
+        if let Some(candidate) = next {
+          return Some(candidate.format("%Y-%m-%d").to_string());
         }
       }
       return None;
@@ -1038,535 +3446,3277 @@ fn compute_next_repeat_date(task: &TaskItem) -> Option<String> {
   Some(next.format("%Y-%m-%d").to_string())
 }
 
+/// Like `compute_next_repeat_date`, but keeps stepping through the rule until
+/// the occurrence lands on or after `earliest`, so a long-overdue task does not
+/// spawn a follow-up that is already overdue.
+fn compute_next_repeat_date_from(task: &TaskItem, earliest: NaiveDate) -> Option<String> {
+  let mut probe = task.clone();
+  let mut next = compute_next_repeat_date(&probe)?;
+  for _ in 0..MAX_REPEAT_CATCH_UP_STEPS {
+    if parse_date_ymd(&next)? >= earliest {
+      break;
+    }
+    probe.due_date = Some(next);
+    next = compute_next_repeat_date(&probe)?;
+  }
+  Some(next)
+}
+
 #[tauri::command]
 fn get_app_snapshot(db: State<'_, DbState>) -> Result<AppSnapshot, String> {
-  let conn = open_connection(&db.db_path)?;
+  let conn = lock_db(&db)?;
 
-  Ok(AppSnapshot {
-    lists: load_lists(&conn)?,
-    tasks: load_tasks(&conn)?,
-    schemes: load_schemes(&conn)?,
-  })
+  load_app_snapshot(&conn)
+}
+
+#[tauri::command]
+fn get_cached_snapshot(db: State<'_, DbState>) -> Option<AppSnapshot> {
+  read_snapshot_cache(&db.cache_path)
+}
+
+/// Argon2id with pinned parameters, so a crate upgrade that changes the defaults
+/// can't lock anyone out of older backups.
+fn derive_backup_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+  let params = Params::new(19_456, 2, 1, Some(32))
+    .map_err(|err| format!("Failed to configure key derivation: {err}"))?;
+  let mut key = [0u8; 32];
+  Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+    .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+    .map_err(|err| format!("Failed to derive backup key: {err}"))?;
+  Ok(key)
+}
+
+fn encrypt_backup(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>, String> {
+  let mut salt = [0u8; BACKUP_SALT_LEN];
+  OsRng.fill_bytes(&mut salt);
+  let key = derive_backup_key(passphrase, &salt)?;
+  let cipher = Aes256Gcm::new_from_slice(&key).map_err(|err| format!("Failed to create cipher: {err}"))?;
+  let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+  let ciphertext = cipher
+    .encrypt(&nonce, plaintext)
+    .map_err(|err| format!("Failed to encrypt backup: {err}"))?;
+
+  let mut output = Vec::with_capacity(ENCRYPTED_BACKUP_MAGIC.len() + salt.len() + nonce.len() + ciphertext.len());
+  output.extend_from_slice(ENCRYPTED_BACKUP_MAGIC);
+  output.extend_from_slice(&salt);
+  output.extend_from_slice(&nonce);
+  output.extend_from_slice(&ciphertext);
+  Ok(output)
+}
+
+/// `content` must start with `ENCRYPTED_BACKUP_MAGIC`. A wrong passphrase fails the
+/// GCM tag check, which is reported as such instead of as unreadable JSON.
+fn decrypt_backup(content: &[u8], passphrase: &str) -> Result<Vec<u8>, String> {
+  let body = &content[ENCRYPTED_BACKUP_MAGIC.len()..];
+  if body.len() < BACKUP_SALT_LEN + BACKUP_NONCE_LEN {
+    return Err("Encrypted backup file is truncated".to_string());
+  }
+  let (salt, rest) = body.split_at(BACKUP_SALT_LEN);
+  let (nonce, ciphertext) = rest.split_at(BACKUP_NONCE_LEN);
+  let key = derive_backup_key(passphrase, salt)?;
+  let cipher = Aes256Gcm::new_from_slice(&key).map_err(|err| format!("Failed to create cipher: {err}"))?;
+  cipher
+    .decrypt(Nonce::from_slice(nonce), ciphertext)
+    .map_err(|_| "Incorrect backup passphrase or corrupted backup file".to_string())
 }
 
 #[tauri::command]
-fn export_backup(db: State<'_, DbState>, path: String) -> Result<String, String> {
+fn export_backup(
+  db: State<'_, DbState>,
+  path: String,
+  passphrase: Option<String>,
+) -> Result<String, String> {
   let output_path = PathBuf::from(path.trim());
   if output_path.as_os_str().is_empty() {
     return Err("Backup path is required".to_string());
   }
 
-  let conn = open_connection(&db.db_path)?;
-  let snapshot = AppSnapshot {
-    lists: load_lists(&conn)?,
-    tasks: load_tasks(&conn)?,
-    schemes: load_schemes(&conn)?,
-  };
+  let conn = lock_db(&db)?;
+  let snapshot = load_app_snapshot(&conn)?;
 
   let payload = BackupPayload {
-    version: 1,
+    version: BACKUP_VERSION,
     exported_at: chrono::Utc::now().to_rfc3339(),
     snapshot,
+    notes: load_task_notes(&conn)?,
   };
 
-  let content =
-    serde_json::to_string_pretty(&payload).map_err(|err| format!("Failed to encode backup: {err}"))?;
+  let mut content =
+    serde_json::to_vec_pretty(&payload).map_err(|err| format!("Failed to encode backup: {err}"))?;
+  if let Some(passphrase) = passphrase.as_deref().filter(|value| !value.is_empty()) {
+    content = encrypt_backup(&content, passphrase)?;
+  }
   fs::write(&output_path, content).map_err(|err| format!("Failed to write backup file: {err}"))?;
 
   Ok(output_path.to_string_lossy().to_string())
 }
 
-#[tauri::command]
-fn import_backup(
-  db: State<'_, DbState>,
-  scheduler: State<'_, SchedulerState>,
-  path: String,
-) -> Result<AppSnapshot, String> {
-  let input_path = PathBuf::from(path.trim());
-  if input_path.as_os_str().is_empty() {
-    return Err("Backup path is required".to_string());
+fn escape_markdown(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for ch in text.chars() {
+    if "\\`*_{}[]()#+-.!|<>~".contains(ch) {
+      escaped.push('\\');
+    }
+    escaped.push(ch);
   }
+  escaped
+}
 
-  let content = fs::read_to_string(&input_path)
-    .map_err(|err| format!("Failed to read backup file: {err}"))?;
-  let payload: BackupPayload =
-    serde_json::from_str(&content).map_err(|err| format!("Failed to parse backup file: {err}"))?;
-
-  if payload.version != 1 {
-    return Err("Unsupported backup version".to_string());
+fn render_markdown_task(out: &mut String, task: &TaskItem) {
+  let checkbox = if task.completed { "[x]" } else { "[ ]" };
+  let mut meta = Vec::new();
+  if let Some(due_date) = task.due_date.as_deref() {
+    match task.time.as_deref() {
+      Some(time) => meta.push(format!("due {due_date} {time}")),
+      None => meta.push(format!("due {due_date}")),
+    }
   }
-  if payload.snapshot.lists.is_empty() {
-    return Err("Backup data is invalid: lists cannot be empty".to_string());
+  let priority = match task.priority {
+    Priority::Low => "low",
+    Priority::Normal => "normal",
+    Priority::High => "high",
+  };
+  meta.push(format!("{priority} priority"));
+
+  out.push_str(&format!("- {checkbox} {} ({})\n", escape_markdown(&task.title), meta.join(", ")));
+  if let Some(detail) = task.detail.as_deref() {
+    for line in detail.lines() {
+      out.push_str(&format!("  {}\n", escape_markdown(line)));
+    }
   }
+}
 
-  let mut conn = open_connection(&db.db_path)?;
-  persist_snapshot(&mut conn, &payload.snapshot)?;
+/// Writes tasks grouped by list as a markdown checklist, limited to one list (or
+/// `UNFILED_LIST_KEY`) when `list_id` is given.
+#[tauri::command]
+fn export_markdown(db: State<'_, DbState>, list_id: Option<String>, path: String) -> Result<String, String> {
+  let output_path = PathBuf::from(path.trim());
+  if output_path.as_os_str().is_empty() {
+    return Err("Export path is required".to_string());
+  }
+
+  let conn = lock_db(&db)?;
+  let lists = load_lists(&conn)?;
+  let tasks = load_tasks(&conn)?;
+  let list_key = |task: &TaskItem| {
+    task
+      .list_id
+      .clone()
+      .filter(|id| lists.iter().any(|list| &list.id == id))
+      .unwrap_or_else(|| UNFILED_LIST_KEY.to_string())
+  };
+
+  let mut groups: Vec<(String, String)> = lists
+    .iter()
+    .map(|list| (list.id.clone(), format!("{} {}", list.icon, escape_markdown(&list.name))))
+    .collect();
+  groups.push((UNFILED_LIST_KEY.to_string(), "Unfiled".to_string()));
+  if let Some(list_id) = list_id.as_deref() {
+    groups.retain(|(id, _)| id == list_id);
+    if groups.is_empty() {
+      return Err("List not found".to_string());
+    }
+  }
+
+  let in_scope: Vec<&TaskItem> = tasks
+    .iter()
+    .filter(|task| groups.iter().any(|(id, _)| *id == list_key(task)))
+    .collect();
+  let completed = in_scope.iter().filter(|task| task.completed).count();
+
+  let mut out = String::from("# LinkFlow report\n\n");
+  out.push_str(&format!(
+    "Exported {}. {} open, {} completed, {} total.\n",
+    Local::now().format("%Y-%m-%d %H:%M"),
+    in_scope.len() - completed,
+    completed,
+    in_scope.len()
+  ));
+  for (id, heading) in &groups {
+    let group_tasks: Vec<&&TaskItem> = in_scope.iter().filter(|task| list_key(task) == *id).collect();
+    if group_tasks.is_empty() {
+      continue;
+    }
+    out.push_str(&format!("\n## {heading}\n\n"));
+    for task in group_tasks {
+      render_markdown_task(&mut out, task);
+    }
+  }
+
+  fs::write(&output_path, out).map_err(|err| format!("Failed to write markdown export: {err}"))?;
+  Ok(output_path.to_string_lossy().to_string())
+}
+
+/// Exports a copy of the SQLite database, so tables that `AppSnapshot` does not
+/// carry (history, snoozes, fired reminders) survive as well.
+#[tauri::command]
+fn export_db_file(db: State<'_, DbState>, path: String) -> Result<String, String> {
+  let output_path = PathBuf::from(path.trim());
+  if output_path.as_os_str().is_empty() {
+    return Err("Export path is required".to_string());
+  }
+  if output_path == db.db_path {
+    return Err("Export path must differ from the live database".to_string());
+  }
+  let file_name = output_path
+    .file_name()
+    .ok_or_else(|| "Export path must name a file".to_string())?;
+  let tmp_path = output_path.with_file_name(format!("{}.tmp", file_name.to_string_lossy()));
+
+  // Probe the destination before touching the database. The copy reads one consistent
+  // snapshot, so writers on the scheduler and cache connections can't tear it.
+  let result = fs::File::create(&tmp_path)
+    .map_err(|err| format!("Export destination is not writable: {err}"))
+    .and_then(|_| copy_database_file(&db.db_path, &tmp_path))
+    .and_then(|_| {
+      fs::rename(&tmp_path, &output_path).map_err(|err| format!("Failed to move database export into place: {err}"))
+    });
+  if result.is_err() {
+    let _ = fs::remove_file(&tmp_path);
+  }
+  result?;
+
+  Ok(output_path.to_string_lossy().to_string())
+}
+
+fn load_action_param_mismatches(conn: &Connection) -> Result<Vec<ActionMismatch>, String> {
+  let mut stmt = conn
+    .prepare(
+      "SELECT a.task_id, t.title, a.position, a.scheme_id, s.name, s.template, a.params
+       FROM task_actions a
+       JOIN tasks t ON t.id = a.task_id
+       JOIN schemes s ON s.id = a.scheme_id
+       WHERE t.deleted_at IS NULL
+       ORDER BY t.rowid ASC, a.position ASC",
+    )
+    .map_err(|err| format!("Failed to query task actions: {err}"))?;
+  let rows = stmt
+    .query_map([], |row| {
+      let template: String = row.get(5)?;
+      let params_json: String = row.get(6)?;
+      let params: Vec<String> = serde_json::from_str(&params_json).unwrap_or_default();
+      Ok(ActionMismatch {
+        task_id: row.get(0)?,
+        task_title: row.get(1)?,
+        position: row.get(2)?,
+        scheme_id: row.get(3)?,
+        scheme_name: row.get(4)?,
+        expected: count_template_params(&template),
+        actual: params.len(),
+      })
+    })
+    .map_err(|err| format!("Failed to map task actions: {err}"))?;
+
+  let mut mismatches = Vec::new();
+  for row in rows {
+    let item = row.map_err(|err| format!("Failed to read action row: {err}"))?;
+    if item.expected != item.actual {
+      mismatches.push(item);
+    }
+  }
+  Ok(mismatches)
+}
+
+#[tauri::command]
+fn action_param_mismatches(db: State<'_, DbState>) -> Result<Vec<ActionMismatch>, String> {
+  let conn = lock_db(&db)?;
+  load_action_param_mismatches(&conn)
+}
+
+/// Reconciles app state with a database that may have been edited by hand: repairs what
+/// has a canonical form, reports rule and reminder problems, and reschedules.
+#[tauri::command]
+fn rebuild(db: State<'_, DbState>, scheduler: State<'_, SchedulerState>) -> Result<RebuildReport, String> {
+  let conn = lock_db(&db)?;
+  let mut report = RebuildReport {
+    integrity_problems: run_integrity_check(&conn)?,
+    schemes_normalized: normalize_stored_scheme_kinds(&conn)?,
+    ..RebuildReport::default()
+  };
+
+  for task in load_tasks(&conn)? {
+    if let Err(message) = validate_repeat_rule(&task.repeat_rule) {
+      report.invalid_repeat_rules.push(TaskIssue {
+        task_id: task.id.clone(),
+        message,
+      });
+    }
+    if let Err(message) = normalize_reminder(&task.reminder) {
+      report.invalid_reminders.push(TaskIssue {
+        task_id: task.id.clone(),
+        message,
+      });
+    }
+  }
+  report.unschedulable_reminders = load_unschedulable_reminders(&conn)?
+    .into_iter()
+    .map(|task| task.id)
+    .collect();
+  report.action_param_mismatches = load_action_param_mismatches(&conn)?;
+
+  scheduler_wakeup(&scheduler);
+  mark_snapshot_dirty(&db);
+  Ok(report)
+}
+
+/// Read-only health check of the live database; unlike `rebuild` it repairs nothing.
+#[tauri::command]
+fn check_database_integrity(db: State<'_, DbState>) -> Result<IntegrityReport, String> {
+  let conn = lock_db(&db)?;
+  let problems = run_integrity_check(&conn)?;
+  let foreign_key_violations = run_foreign_key_check(&conn)?;
+  Ok(IntegrityReport {
+    ok: problems.is_empty() && foreign_key_violations.is_empty(),
+    problems,
+    foreign_key_violations,
+  })
+}
+
+/// Size of the database file once the WAL has been folded back into it.
+fn checkpointed_file_size(conn: &Connection, db_path: &Path) -> Result<u64, String> {
+  conn
+    .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+    .map_err(|err| format!("Failed to checkpoint database: {err}"))?;
+  fs::metadata(db_path)
+    .map(|metadata| metadata.len())
+    .map_err(|err| format!("Failed to read database file size: {err}"))
+}
+
+/// Prunes expired fired-reminder records, then rewrites the database file to
+/// reclaim free pages and refreshes the query planner statistics. `VACUUM`
+/// rewrites the whole file, so UI commands and scheduler writes briefly wait
+/// on it; call it while no reminder is about to fire.
+#[tauri::command]
+fn compact_database(db: State<'_, DbState>) -> Result<CompactResult, String> {
+  let conn = lock_db(&db)?;
+  let size_before = checkpointed_file_size(&conn, &db.db_path)?;
+
+  cleanup_old_fired_reminders(&conn, now_epoch_ms())?;
+  conn
+    .execute_batch("VACUUM; ANALYZE; PRAGMA optimize;")
+    .map_err(|err| format!("Failed to compact database: {err}"))?;
+
+  Ok(CompactResult {
+    size_before,
+    size_after: checkpointed_file_size(&conn, &db.db_path)?,
+  })
+}
+
+/// `path` with `suffix` appended to the file name, e.g. the `-wal` beside a database.
+fn sqlite_sidecar_path(path: &Path, suffix: &str) -> PathBuf {
+  let mut name = path.as_os_str().to_owned();
+  name.push(suffix);
+  PathBuf::from(name)
+}
+
+/// Writes a self-contained copy of `source` to `dest` with `VACUUM INTO`. Unlike a
+/// plain file copy this reads through the source's `-wal`, so commits that were
+/// never checkpointed into the main file are kept.
+fn copy_database_file(source: &Path, dest: &Path) -> Result<(), String> {
+  let dest_name = dest
+    .to_str()
+    .ok_or_else(|| "Database staging path is not valid UTF-8".to_string())?;
+  let _ = fs::remove_file(dest);
+  let conn = Connection::open_with_flags(source, OpenFlags::SQLITE_OPEN_READ_ONLY)
+    .map_err(|err| format!("Failed to open database file: {err}"))?;
+  conn
+    .execute("VACUUM INTO ?1", params![dest_name])
+    .map_err(|err| format!("Failed to stage database file: {err}"))?;
+  Ok(())
+}
+
+/// Moves `staging_path` into place as the live database behind `conn`. The current
+/// file is kept as a timestamped `.bak`, with its `-wal`/`-shm` moved alongside so
+/// they can never attach to the new file, and `conn` is reopened on whichever
+/// file ends up in place.
+fn swap_in_database_file(conn: &mut Connection, db_path: &Path, staging_path: &Path) -> Result<(), String> {
+  conn
+    .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+    .map_err(|err| format!("Failed to checkpoint database: {err}"))?;
+
+  let placeholder =
+    Connection::open_in_memory().map_err(|err| format!("Failed to open placeholder database: {err}"))?;
+  drop(std::mem::replace(conn, placeholder));
+
+  let backup_path = db_path.with_extension(format!("db.bak-{}", Local::now().format("%Y%m%d%H%M%S")));
+  let swapped = fs::copy(db_path, &backup_path)
+    .map_err(|err| format!("Failed to back up current database: {err}"))
+    .and_then(|_| {
+      for suffix in ["-wal", "-shm"] {
+        let sidecar = sqlite_sidecar_path(db_path, suffix);
+        if sidecar.exists() {
+          fs::rename(&sidecar, sqlite_sidecar_path(&backup_path, suffix))
+            .map_err(|err| format!("Failed to move aside {}: {err}", sidecar.display()))?;
+        }
+      }
+      fs::rename(staging_path, db_path).map_err(|err| format!("Failed to replace database file: {err}"))
+    });
+  if swapped.is_err() {
+    let _ = fs::remove_file(staging_path);
+  }
+  *conn = open_connection(db_path)?;
+  swapped
+}
+
+/// Replaces the live database with a raw SQLite file. The source is validated
+/// and upgraded in a staging copy first, and the current file is kept as a
+/// timestamped `.bak` next to it, so a failure at any step leaves the
+/// original database in place.
+#[tauri::command]
+fn restore_db_file(
+  db: State<'_, DbState>,
+  scheduler: State<'_, SchedulerState>,
+  path: String,
+) -> Result<AppSnapshot, String> {
+  let input_path = PathBuf::from(path.trim());
+  if input_path.as_os_str().is_empty() {
+    return Err("Database file path is required".to_string());
+  }
+  if input_path == db.db_path {
+    return Err("Cannot restore the live database onto itself".to_string());
+  }
+
+  let mut conn = lock_db(&db)?;
+  validate_restore_source(&input_path, schema_user_version(&conn)?)?;
+
+  let staging_path = db.db_path.with_extension("db.restore");
+  if let Err(error) = copy_database_file(&input_path, &staging_path).and_then(|_| init_database(&staging_path)) {
+    let _ = fs::remove_file(&staging_path);
+    return Err(error);
+  }
+  swap_in_database_file(&mut conn, &db.db_path, &staging_path)?;
+
+  scheduler_wakeup(&scheduler);
+  mark_snapshot_dirty(&db);
+
+  load_app_snapshot(&conn)
+}
+
+/// Upgrades a raw backup document of any supported version to the current
+/// `BackupPayload`, filling fields older versions lacked with their defaults.
+fn migrate_backup_payload(mut raw: serde_json::Value) -> Result<BackupPayload, String> {
+  let mut version = raw
+    .get("version")
+    .and_then(serde_json::Value::as_u64)
+    .ok_or_else(|| "Backup file has no version".to_string())?;
+
+  if version == 1 {
+    let tasks = raw
+      .pointer_mut("/snapshot/tasks")
+      .and_then(serde_json::Value::as_array_mut)
+      .map(|tasks| tasks.iter_mut().filter_map(serde_json::Value::as_object_mut));
+    for task in tasks.into_iter().flatten() {
+      task
+        .entry("priority")
+        .or_insert_with(|| serde_json::json!(Priority::default()));
+      task.entry("tags").or_insert_with(|| serde_json::json!([]));
+    }
+    version = 2;
+  }
+  if version == 2 {
+    // Version 3 added task notes; older backups simply have none.
+    version = 3;
+  }
+  if version != u64::from(BACKUP_VERSION) {
+    return Err("Unsupported backup version".to_string());
+  }
+  raw["version"] = serde_json::json!(version);
+
+  serde_json::from_value(raw).map_err(|err| format!("Failed to parse backup file: {err}"))
+}
+
+#[tauri::command]
+fn import_backup(
+  db: State<'_, DbState>,
+  scheduler: State<'_, SchedulerState>,
+  path: String,
+  import_mode: Option<ImportMode>,
+  passphrase: Option<String>,
+) -> Result<BackupImportResult, String> {
+  let input_path = PathBuf::from(path.trim());
+  if input_path.as_os_str().is_empty() {
+    return Err("Backup path is required".to_string());
+  }
+
+  let mut content =
+    fs::read(&input_path).map_err(|err| format!("Failed to read backup file: {err}"))?;
+  if content.starts_with(ENCRYPTED_BACKUP_MAGIC) {
+    let Some(passphrase) = passphrase.as_deref().filter(|value| !value.is_empty()) else {
+      return Err("Backup file is encrypted; a passphrase is required".to_string());
+    };
+    content = decrypt_backup(&content, passphrase)?;
+  }
+  let raw: serde_json::Value =
+    serde_json::from_slice(&content).map_err(|err| format!("Failed to parse backup file: {err}"))?;
+  let payload = migrate_backup_payload(raw)?;
+  if payload.snapshot.lists.is_empty() {
+    return Err("Backup data is invalid: lists cannot be empty".to_string());
+  }
+
+  let mut conn = lock_db(&db)?;
+  let (lists, schemes, tasks) = match import_mode.unwrap_or_default() {
+    ImportMode::Replace => {
+      persist_snapshot(&mut conn, &payload.snapshot, &payload.notes)?;
+      let added = |count: usize| MergeCounts { added: count, updated: 0 };
+      (
+        added(payload.snapshot.lists.len()),
+        added(payload.snapshot.schemes.len()),
+        added(payload.snapshot.tasks.len()),
+      )
+    }
+    ImportMode::Merge => merge_snapshot(&mut conn, &payload.snapshot, &payload.notes)?,
+  };
+  scheduler_wakeup(&scheduler);
+  mark_snapshot_dirty(&db);
+
+  Ok(BackupImportResult {
+    snapshot: load_app_snapshot(&conn)?,
+    lists,
+    schemes,
+    tasks,
+  })
+}
+
+#[tauri::command]
+fn debug_next_reminder(db: State<'_, DbState>) -> Result<Option<DebugNextReminder>, String> {
+  let now = now_epoch_ms();
+  let next = query_next_reminder(&db.db_path, now)?;
+  Ok(next.map(|item| debug_reminder(item, now)))
+}
+
+/// Dry run of the scheduler's view of one task: when its reminder falls, whether it
+/// already fired, and where its repeat rule would move it next. Writes nothing.
+#[tauri::command]
+fn debug_task_reminders(db: State<'_, DbState>, task_id: String) -> Result<DebugTaskReminders, String> {
+  let conn = lock_db(&db)?;
+  let now = now_epoch_ms();
+  let task = fetch_task_by_id(&conn, &task_id)?;
+  let dependency_completed_at = match task
+    .reminder
+    .as_ref()
+    .and_then(|reminder| reminder.depends_on_task_id.as_deref())
+  {
+    Some(depends_on) => conn
+      .query_row(
+        "SELECT completed_at FROM tasks WHERE id = ?1",
+        params![depends_on],
+        |row| row.get::<_, Option<i64>>(0),
+      )
+      .or_else(|err| match err {
+        rusqlite::Error::QueryReturnedNoRows => Ok(None),
+        other => Err(other),
+      })
+      .map_err(|err| format!("Failed to load reminder dependency: {err}"))?,
+    None => None,
+  };
+
+  let remind_at = if task.completed {
+    None
+  } else {
+    compute_remind_at(&task, dependency_completed_at, read_dst_ambiguity(&conn)?)
+  };
+  let fired = match remind_at {
+    Some(remind_at_ms) => is_reminder_fired(&conn, &task.id, remind_at_ms)?,
+    None => false,
+  };
+  let grace_ms = reminder_grace_ms(&conn)?;
+  let in_grace_window = remind_at.is_some_and(|remind_at_ms| remind_at_ms <= now && remind_at_ms >= now - grace_ms);
+
+  Ok(DebugTaskReminders {
+    next_repeat_date: compute_next_repeat_date(&task),
+    task_id: task.id,
+    now,
+    remind_at,
+    fired,
+    in_grace_window,
+  })
+}
+
+/// The next `limit` pending reminders, soonest first, for an agenda view.
+#[tauri::command]
+fn list_upcoming_reminders(db: State<'_, DbState>, limit: usize) -> Result<Vec<DebugNextReminder>, String> {
+  let conn = lock_db(&db)?;
+  let now = now_epoch_ms();
+  let mut candidates = collect_reminder_candidates(&conn, now, false)?;
+  candidates.sort_by_key(|candidate| candidate.remind_at_ms);
+  Ok(
+    candidates
+      .into_iter()
+      .take(limit)
+      .map(|candidate| debug_reminder(candidate, now))
+      .collect(),
+  )
+}
+
+/// The reminder that will actually fire for a task. A list's default reminder is
+/// copied onto new tasks when they are created, so the task's stored reminder is
+/// already the effective one; there is no global default.
+#[tauri::command]
+fn effective_reminder(db: State<'_, DbState>, task_id: String) -> Result<Option<Reminder>, String> {
+  let conn = lock_db(&db)?;
+  let task = fetch_task_by_id(&conn, &task_id)?;
+  Ok(task.reminder)
+}
+
+/// Every pending reminder due between now and `until_ms`, soonest first, so the UI can
+/// warn about reminders that would be missed while the app is closed.
+#[tauri::command]
+fn reminders_until(db: State<'_, DbState>, until_ms: i64) -> Result<Vec<DebugNextReminder>, String> {
+  let conn = lock_db(&db)?;
+  let now = now_epoch_ms();
+  let mut candidates: Vec<ReminderCandidate> = collect_reminder_candidates(&conn, now, false)?
+    .into_iter()
+    .filter(|candidate| candidate.remind_at_ms <= until_ms)
+    .collect();
+  candidates.sort_by_key(|candidate| candidate.remind_at_ms);
+  Ok(
+    candidates
+      .into_iter()
+      .map(|candidate| debug_reminder(candidate, now))
+      .collect(),
+  )
+}
+
+/// Unfired reminders already past but still inside the grace window, oldest first: the
+/// ones the scheduler will fire immediately on its next wake.
+#[tauri::command]
+fn grace_window_reminders(db: State<'_, DbState>) -> Result<Vec<DebugNextReminder>, String> {
+  let conn = lock_db(&db)?;
+  let now = now_epoch_ms();
+  let mut candidates: Vec<ReminderCandidate> = collect_reminder_candidates(&conn, now, false)?
+    .into_iter()
+    .filter(|candidate| candidate.remind_at_ms <= now)
+    .collect();
+  candidates.sort_by_key(|candidate| candidate.remind_at_ms);
+  Ok(
+    candidates
+      .into_iter()
+      .map(|candidate| debug_reminder(candidate, now))
+      .collect(),
+  )
+}
+
+/// Pending reminders keyed by list id; tasks without a list use `"unfiled"`.
+#[tauri::command]
+fn reminders_by_list(db: State<'_, DbState>) -> Result<HashMap<String, Vec<DebugNextReminder>>, String> {
+  let conn = lock_db(&db)?;
+  let now = now_epoch_ms();
+  let mut candidates = collect_reminder_candidates(&conn, now, false)?;
+  candidates.sort_by_key(|candidate| candidate.remind_at_ms);
+
+  let mut grouped: HashMap<String, Vec<DebugNextReminder>> = HashMap::new();
+  for candidate in candidates {
+    let key = candidate
+      .list_id
+      .clone()
+      .unwrap_or_else(|| UNFILED_LIST_KEY.to_string());
+    grouped.entry(key).or_default().push(debug_reminder(candidate, now));
+  }
+  Ok(grouped)
+}
+
+#[tauri::command]
+fn task_history(db: State<'_, DbState>, task_id: String) -> Result<Vec<TaskChange>, String> {
+  let conn = lock_db(&db)?;
+  load_task_changes(&conn, &task_id)
+}
+
+fn read_task_note(conn: &Connection, task_id: &str) -> Result<Option<String>, String> {
+  conn
+    .query_row(
+      "SELECT body FROM task_notes WHERE task_id = ?1",
+      params![task_id],
+      |row| row.get(0),
+    )
+    .map(Some)
+    .or_else(|err| match err {
+      rusqlite::Error::QueryReturnedNoRows => Ok(None),
+      other => Err(format!("Failed to load task note: {other}")),
+    })
+}
+
+fn write_task_note(conn: &Connection, task_id: &str, body: Option<&str>) -> Result<(), String> {
+  match body {
+    Some(body) => conn.execute(
+      "INSERT INTO task_notes (task_id, body) VALUES (?1, ?2)
+       ON CONFLICT(task_id) DO UPDATE SET body = excluded.body",
+      params![task_id, body],
+    ),
+    None => conn.execute("DELETE FROM task_notes WHERE task_id = ?1", params![task_id]),
+  }
+  .map_err(|err| format!("Failed to save task note: {err}"))?;
+  Ok(())
+}
+
+#[tauri::command]
+fn get_task_note(db: State<'_, DbState>, task_id: String) -> Result<Option<String>, String> {
+  let conn = lock_db(&db)?;
+  fetch_task_by_id(&conn, &task_id)?;
+  read_task_note(&conn, &task_id)
+}
+
+/// Replaces a task's note; an empty or missing body removes it. The history
+/// entry records that the note changed, not its text.
+#[tauri::command]
+fn set_task_note(db: State<'_, DbState>, task_id: String, body: Option<String>) -> Result<(), String> {
+  let body = body.filter(|value| !value.trim().is_empty());
+  let conn = lock_db(&db)?;
+  fetch_task_by_id(&conn, &task_id)?;
+  if read_task_note(&conn, &task_id)? == body {
+    return Ok(());
+  }
+
+  write_task_note(&conn, &task_id, body.as_deref())?;
+  record_task_change(&conn, &task_id, "updated", Some("note"), None, None)?;
+  Ok(())
+}
+
+/// Summary kept in `detail` when `move_long_details_to_notes` moves the full text out.
+const DETAIL_SUMMARY_CHARS: usize = 80;
+
+/// One-off cleanup for details written before notes existed: any task whose
+/// `detail` is longer than `min_chars` (default 500) has the full text
+/// appended to its note and `detail` cut down to a one-line summary. Returns
+/// the number of tasks changed.
+#[tauri::command]
+fn move_long_details_to_notes(db: State<'_, DbState>, min_chars: Option<usize>) -> Result<usize, String> {
+  let min_chars = min_chars.unwrap_or(500);
+  let mut conn = lock_db(&db)?;
+  let tx = conn
+    .transaction()
+    .map_err(|err| format!("Failed to start transaction: {err}"))?;
+
+  let long_details: Vec<(String, String)> = {
+    let mut stmt = tx
+      .prepare("SELECT id, detail FROM tasks WHERE detail IS NOT NULL AND LENGTH(detail) > ?1")
+      .map_err(|err| format!("Failed to query task details: {err}"))?;
+    let rows = stmt
+      .query_map(params![min_chars as i64], |row| Ok((row.get(0)?, row.get(1)?)))
+      .map_err(|err| format!("Failed to map task details: {err}"))?;
+    let mut long_details = Vec::new();
+    for row in rows {
+      long_details.push(row.map_err(|err| format!("Failed to read task detail row: {err}"))?);
+    }
+    long_details
+  };
+
+  for (task_id, detail) in &long_details {
+    let body = match read_task_note(&tx, task_id)? {
+      Some(existing) => format!("{existing}\n\n{detail}"),
+      None => detail.clone(),
+    };
+    write_task_note(&tx, task_id, Some(&body))?;
+
+    let first_line = detail.lines().find(|line| !line.trim().is_empty()).unwrap_or_default().trim();
+    let mut summary: String = first_line.chars().take(DETAIL_SUMMARY_CHARS).collect();
+    if summary.len() < detail.trim().len() {
+      summary.push('…');
+    }
+    tx
+      .execute(
+        "UPDATE tasks SET detail = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+        params![task_id, summary],
+      )
+      .map_err(|err| format!("Failed to shorten task detail: {err}"))?;
+    // The full text now lives in the note; the log only needs to say it moved.
+    let moved = format!("[moved to note: {} chars]", detail.chars().count());
+    record_task_change(&tx, task_id, "updated", Some("detail"), Some(moved), Some(summary))?;
+  }
+
+  tx
+    .commit()
+    .map_err(|err| format!("Failed to commit detail migration: {err}"))?;
+  if !long_details.is_empty() {
+    mark_snapshot_dirty(&db);
+  }
+  Ok(long_details.len())
+}
+
+fn write_snooze(
+  conn: &Connection,
+  task_id: &str,
+  original_remind_at: i64,
+  snoozed_until: i64,
+  now: i64,
+) -> Result<(), String> {
+  conn
+    .execute(
+      "INSERT OR REPLACE INTO snoozed_reminders (task_id, original_remind_at, snoozed_until, created_at)
+       VALUES (?1, ?2, ?3, ?4)",
+      params![task_id, original_remind_at, snoozed_until, now],
+    )
+    .map_err(|err| format!("Failed to snooze reminder: {err}"))?;
+  Ok(())
+}
+
+/// Re-fires the task's most recent reminder `minutes` from now and returns the new time.
+/// The snooze is keyed on that fired instant, so snoozing again replaces it.
+#[tauri::command]
+fn snooze_reminder(
+  db: State<'_, DbState>,
+  scheduler: State<'_, SchedulerState>,
+  task_id: String,
+  minutes: i64,
+) -> Result<i64, String> {
+  if minutes <= 0 {
+    return Err("Snooze minutes must be positive".to_string());
+  }
+
+  let conn = lock_db(&db)?;
+  let task = fetch_task_by_id(&conn, &task_id)?;
+  if task.completed {
+    return Err("Cannot snooze a completed task".to_string());
+  }
+
+  let now = now_epoch_ms();
+  let last_fired: Option<i64> = conn
+    .query_row(
+      "SELECT remind_at FROM fired_reminders WHERE task_id = ?1 ORDER BY fired_at DESC LIMIT 1",
+      params![task_id],
+      |row| row.get(0),
+    )
+    .map(Some)
+    .or_else(|err| match err {
+      rusqlite::Error::QueryReturnedNoRows => Ok(None),
+      other => Err(other),
+    })
+    .map_err(|err| format!("Failed to read fired reminder: {err}"))?;
+  let snoozed_until = now + minutes * 60_000;
+  write_snooze(&conn, &task_id, last_fired.unwrap_or(now), snoozed_until, now)?;
+
+  scheduler_wakeup(&scheduler);
+  Ok(snoozed_until)
+}
+
+/// Runs a notification button for the reminder originally due at `remind_at`.
+/// Completing an already completed task is a no-op rather than reopening it, and
+/// snoozing re-fires the reminder `REMINDER_ACTION_SNOOZE_MINUTES` from now.
+/// The plugin only shows action buttons on mobile; on desktop the same actions are
+/// offered by the in-app reminder cards, fed by `REMINDER_FIRED_EVENT`.
+#[tauri::command]
+fn handle_reminder_action(
+  db: State<'_, DbState>,
+  scheduler: State<'_, SchedulerState>,
+  task_id: String,
+  remind_at: i64,
+  action: ReminderAction,
+) -> Result<(), String> {
+  let conn = lock_db(&db)?;
+  let task = fetch_task_by_id(&conn, &task_id)?;
+  if task.completed {
+    return Ok(());
+  }
+
+  match action {
+    ReminderAction::Complete => {
+      drop(conn);
+      toggle_task_completed(db, scheduler, task_id)?;
+    }
+    ReminderAction::Snooze => {
+      let now = now_epoch_ms();
+      write_snooze(
+        &conn,
+        &task_id,
+        remind_at,
+        now + REMINDER_ACTION_SNOOZE_MINUTES * 60_000,
+        now,
+      )?;
+      scheduler_wakeup(&scheduler);
+    }
+  }
+  Ok(())
+}
+
+/// Snoozes the reminder that was due at `remind_at_ms` until the absolute `until_ms`.
+/// The reminder must have fired, still be pending, or already be snoozed.
+#[tauri::command]
+fn snooze_until(
+  db: State<'_, DbState>,
+  scheduler: State<'_, SchedulerState>,
+  task_id: String,
+  remind_at_ms: i64,
+  until_ms: i64,
+) -> Result<(), String> {
+  let now = now_epoch_ms();
+  if until_ms <= now {
+    return Err("Snooze time must be in the future".to_string());
+  }
+
+  let conn = lock_db(&db)?;
+  let known = is_reminder_fired(&conn, &task_id, remind_at_ms)?
+    || collect_reminder_candidates(&conn, now, false)?
+      .iter()
+      .any(|candidate| {
+        candidate.task_id == task_id
+          && (candidate.remind_at_ms == remind_at_ms || candidate.snoozed_from == Some(remind_at_ms))
+      });
+  if !known {
+    return Err("Reminder is not fired or pending".to_string());
+  }
+
+  // A still-pending original is superseded by the snooze, so keep it from firing too.
+  mark_reminder_fired(&conn, &task_id, remind_at_ms, now)?;
+  write_snooze(&conn, &task_id, remind_at_ms, until_ms, now)?;
+  scheduler_wakeup(&scheduler);
+  Ok(())
+}
+
+#[tauri::command]
+fn list_snoozes(db: State<'_, DbState>) -> Result<Vec<SnoozeEntry>, String> {
+  let conn = lock_db(&db)?;
+  let mut stmt = conn
+    .prepare(
+      "SELECT s.task_id, t.title, s.original_remind_at, s.snoozed_until
+       FROM snoozed_reminders s
+       JOIN tasks t ON t.id = s.task_id
+       WHERE t.deleted_at IS NULL
+       ORDER BY s.snoozed_until ASC",
+    )
+    .map_err(|err| format!("Failed to query snoozes: {err}"))?;
+
+  let rows = stmt
+    .query_map([], |row| {
+      Ok(SnoozeEntry {
+        task_id: row.get(0)?,
+        task_title: row.get(1)?,
+        original_remind_at: row.get(2)?,
+        snoozed_until: row.get(3)?,
+      })
+    })
+    .map_err(|err| format!("Failed to map snoozes: {err}"))?;
+
+  let mut snoozes = Vec::new();
+  for row in rows {
+    snoozes.push(row.map_err(|err| format!("Failed to read snooze row: {err}"))?);
+  }
+  Ok(snoozes)
+}
+
+#[tauri::command]
+fn cancel_snooze(
+  db: State<'_, DbState>,
+  scheduler: State<'_, SchedulerState>,
+  task_id: String,
+  original_remind_at: i64,
+) -> Result<(), String> {
+  let conn = lock_db(&db)?;
+  if delete_snooze(&conn, &task_id, original_remind_at)? == 0 {
+    return Err("Snooze not found".to_string());
+  }
+
+  scheduler_wakeup(&scheduler);
+  Ok(())
+}
+
+/// Shows a reminder notification immediately so users can confirm the OS lets them
+/// through and renders them properly. With a task id it is the exact notification the
+/// scheduler would send for that task; without one it is a generic sample. Nothing is
+/// recorded in `fired_reminders`.
+#[tauri::command]
+fn test_notification(app: AppHandle, db: State<'_, DbState>, task_id: Option<String>) -> Result<(), String> {
+  let now = now_epoch_ms();
+  let candidate = match task_id {
+    Some(task_id) => {
+      let conn = lock_db(&db)?;
+      let task = fetch_task_by_id(&conn, &task_id)?;
+      let list_name = match task.list_id.as_deref() {
+        Some(list_id) => conn
+          .query_row("SELECT name FROM lists WHERE id = ?1", params![list_id], |row| {
+            row.get::<_, String>(0)
+          })
+          .map(Some)
+          .or_else(|err| match err {
+            rusqlite::Error::QueryReturnedNoRows => Ok(None),
+            other => Err(other),
+          })
+          .map_err(|err| format!("Failed to load list name: {err}"))?,
+        None => None,
+      };
+      ReminderCandidate {
+        task_id: task.id,
+        task_title: task.title,
+        task_detail: task.detail,
+        list_id: task.list_id,
+        list_name,
+        due_date: task.due_date.unwrap_or_default(),
+        time: task.time.unwrap_or_default(),
+        remind_at_ms: now,
+        snoozed_from: None,
+      }
+    }
+    None => ReminderCandidate {
+      task_id: String::new(),
+      task_title: "示例任务".to_string(),
+      task_detail: Some("这是一条测试通知，用于确认提醒可以正常显示。".to_string()),
+      list_id: None,
+      list_name: None,
+      due_date: String::new(),
+      time: String::new(),
+      remind_at_ms: now,
+      snoozed_from: None,
+    },
+  };
+  send_task_reminder_notification(&app, &candidate)
+}
+
+#[tauri::command]
+fn notification_permission(app: AppHandle) -> Result<String, String> {
+  app
+    .notification()
+    .permission_state()
+    .map(|state| permission_label(state).to_string())
+    .map_err(|err| format!("Failed to read notification permission: {err}"))
+}
+
+/// Alias of `notification_permission`: "granted", "denied", or "default" (not asked
+/// yet), so the UI can prompt before reminders silently fail to show.
+#[tauri::command]
+fn notification_permission_status(app: AppHandle) -> Result<String, String> {
+  notification_permission(app)
+}
+
+/// Prompts the user where the OS supports it and returns the resulting state.
+#[tauri::command]
+fn request_notification_permission(app: AppHandle) -> Result<String, String> {
+  app
+    .notification()
+    .request_permission()
+    .map(|state| permission_label(state).to_string())
+    .map_err(|err| format!("Failed to request notification permission: {err}"))
+}
+
+#[tauri::command]
+fn get_setting(db: State<'_, DbState>, key: String) -> Result<String, String> {
+  let conn = lock_db(&db)?;
+  read_setting(&conn, &key)
+}
+
+#[tauri::command]
+fn set_setting(
+  db: State<'_, DbState>,
+  scheduler: State<'_, SchedulerState>,
+  key: String,
+  value: String,
+) -> Result<String, String> {
+  let conn = lock_db(&db)?;
+  let value = write_setting(&conn, &key, &value)?;
+  if key == SETTING_REMINDER_GRACE {
+    scheduler_wakeup(&scheduler);
+  }
+  Ok(value)
+}
+
+#[tauri::command]
+fn scheduler_config(db: State<'_, DbState>) -> Result<SchedulerConfig, String> {
+  let conn = lock_db(&db)?;
+  Ok(SchedulerConfig {
+    grace_ms: reminder_grace_ms(&conn)?,
+    retention_ms: fired_retention_ms(&conn)?,
+    missed_reminder_window_ms: i64::from(read_u32_setting(&conn, SETTING_MISSED_REMINDER_WINDOW)?) * 60 * 1000,
+    retry_interval_ms: SCHEDULER_RETRY_MS,
+  })
+}
+
+fn validate_default_reminder_offset(offset: Option<i64>) -> Result<Option<i64>, String> {
+  match offset {
+    Some(minutes) if minutes < 0 => Err("Default reminder offset cannot be negative".to_string()),
+    other => Ok(other),
+  }
+}
+
+/// The reminder a new task inherits from its list: only when it has none of its own,
+/// hasn't opted out, and has both a date and a time for the offset to count back from.
+fn apply_list_default_reminder(conn: &Connection, task: &mut TaskItem) -> Result<(), String> {
+  if task.reminder.is_some() || task.due_date.is_none() || task.time.is_none() {
+    return Ok(());
+  }
+  let Some(list_id) = task.list_id.as_deref() else {
+    return Ok(());
+  };
+  let offset: Option<i64> = conn
+    .query_row(
+      "SELECT default_reminder_offset FROM lists WHERE id = ?1",
+      params![list_id],
+      |row| row.get(0),
+    )
+    .or_else(|err| match err {
+      rusqlite::Error::QueryReturnedNoRows => Ok(None),
+      other => Err(other),
+    })
+    .map_err(|err| format!("Failed to read list default reminder: {err}"))?;
+
+  task.reminder = offset.map(|offset_minutes| Reminder {
+    reminder_type: "relative".to_string(),
+    offset_minutes,
+    ..Reminder::default()
+  });
+  Ok(())
+}
+
+#[tauri::command]
+fn get_list(db: State<'_, DbState>, list_id: String) -> Result<ListItem, String> {
+  let conn = lock_db(&db)?;
+  fetch_list_by_id(&conn, &list_id)
+}
+
+#[tauri::command]
+fn create_list(db: State<'_, DbState>, input: ListInput) -> Result<ListItem, String> {
+  let name = input.name.trim();
+  if name.is_empty() {
+    return Err("List name is required".to_string());
+  }
+
+  let list = ListItem {
+    id: format!("list_{}", Uuid::new_v4()),
+    name: name.to_string(),
+    icon: normalize_list_icon(&input.icon),
+    color: normalize_hex_color(input.color.as_deref())?,
+    default_reminder_offset: validate_default_reminder_offset(input.default_reminder_offset)?,
+  };
+
+  let conn = lock_db(&db)?;
+  conn
+    .execute(
+      "INSERT INTO lists (id, name, icon, color, default_reminder_offset, position)
+       VALUES (?1, ?2, ?3, ?4, ?5, (SELECT COALESCE(MAX(position), -1) + 1 FROM lists))",
+      params![list.id, list.name, list.icon, list.color, list.default_reminder_offset],
+    )
+    .map_err(|err| format!("Failed to create list: {err}"))?;
+
+  mark_snapshot_dirty(&db);
+  Ok(list)
+}
+
+#[tauri::command]
+fn update_list(db: State<'_, DbState>, list_id: String, patch: ListInput) -> Result<ListItem, String> {
+  let name = patch.name.trim();
+  if name.is_empty() {
+    return Err("List name is required".to_string());
+  }
+
+  let list = ListItem {
+    id: list_id.clone(),
+    name: name.to_string(),
+    icon: normalize_list_icon(&patch.icon),
+    color: normalize_hex_color(patch.color.as_deref())?,
+    default_reminder_offset: validate_default_reminder_offset(patch.default_reminder_offset)?,
+  };
+
+  let conn = lock_db(&db)?;
+  let affected = conn
+    .execute(
+      "UPDATE lists SET name = ?2, icon = ?3, color = ?4, default_reminder_offset = ?5 WHERE id = ?1",
+      params![list.id, list.name, list.icon, list.color, list.default_reminder_offset],
+    )
+    .map_err(|err| format!("Failed to update list: {err}"))?;
+
+  if affected == 0 {
+    return Err("List not found".to_string());
+  }
+
+  mark_snapshot_dirty(&db);
+  Ok(list)
+}
+
+#[tauri::command]
+fn update_lists_style(
+  db: State<'_, DbState>,
+  updates: Vec<ListStyleUpdate>,
+) -> Result<Vec<ListItem>, String> {
+  let mut conn = lock_db(&db)?;
+  let tx = conn
+    .transaction()
+    .map_err(|err| format!("Failed to start transaction: {err}"))?;
+
+  for update in &updates {
+    let exists: i64 = tx
+      .query_row(
+        "SELECT EXISTS(SELECT 1 FROM lists WHERE id = ?1)",
+        params![update.id],
+        |row| row.get(0),
+      )
+      .map_err(|err| format!("Failed to look up list: {err}"))?;
+    if exists == 0 {
+      return Err(format!("List not found: {}", update.id));
+    }
+
+    if let Some(icon) = update.icon.as_deref() {
+      tx
+        .execute(
+          "UPDATE lists SET icon = ?2 WHERE id = ?1",
+          params![update.id, normalize_list_icon(icon)],
+        )
+        .map_err(|err| format!("Failed to update list icon: {err}"))?;
+    }
+    if let Some(color) = update.color.as_deref() {
+      tx
+        .execute(
+          "UPDATE lists SET color = ?2 WHERE id = ?1",
+          params![update.id, normalize_hex_color(Some(color))?],
+        )
+        .map_err(|err| format!("Failed to update list color: {err}"))?;
+    }
+  }
+
+  tx
+    .commit()
+    .map_err(|err| format!("Failed to commit list style update: {err}"))?;
+
+  mark_snapshot_dirty(&db);
+
+  load_lists(&conn)
+}
+
+#[tauri::command]
+fn get_scheme(db: State<'_, DbState>, scheme_id: String) -> Result<UrlScheme, String> {
+  let conn = lock_db(&db)?;
+  fetch_scheme_by_id(&conn, &scheme_id)
+}
+
+#[tauri::command]
+fn create_scheme(db: State<'_, DbState>, input: SchemeInput) -> Result<UrlScheme, String> {
+  let name = input.name.trim();
+  let icon = input.icon.trim();
+  let template = input.template.trim();
+  if name.is_empty() || template.is_empty() {
+    return Err("Scheme name and template are required".to_string());
+  }
+
+  let scheme = UrlScheme {
+    id: format!("scheme_{}", Uuid::new_v4()),
+    name: name.to_string(),
+    icon: if icon.is_empty() { "🔗".to_string() } else { icon.to_string() },
+    template: template.to_string(),
+    kind: normalize_scheme_kind(input.kind)?,
+    param_type: normalize_param_type(&input.param_type)?,
+    enabled: input.enabled.unwrap_or(true),
+  };
+
+  let conn = lock_db(&db)?;
+  conn
+    .execute(
+      "INSERT INTO schemes (id, name, icon, template, kind, param_type, enabled, position)
+       VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, (SELECT COALESCE(MAX(position), -1) + 1 FROM schemes))",
+      params![
+        scheme.id,
+        scheme.name,
+        scheme.icon,
+        scheme.template,
+        scheme.kind.to_db(),
+        scheme.param_type,
+        scheme.enabled
+      ],
+    )
+    .map_err(|err| format!("Failed to create scheme: {err}"))?;
+
+  mark_snapshot_dirty(&db);
+  Ok(scheme)
+}
+
+/// Bindings of `scheme_id` carrying fewer than `expected` params.
+fn count_bindings_short_of(conn: &Connection, scheme_id: &str, expected: usize) -> Result<usize, String> {
+  let mut stmt = conn
+    .prepare("SELECT params FROM task_actions WHERE scheme_id = ?1")
+    .map_err(|err| format!("Failed to query scheme bindings: {err}"))?;
+  let rows = stmt
+    .query_map(params![scheme_id], |row| row.get::<_, String>(0))
+    .map_err(|err| format!("Failed to map scheme bindings: {err}"))?;
+
+  let mut short = 0;
+  for row in rows {
+    let params_json = row.map_err(|err| format!("Failed to read scheme binding: {err}"))?;
+    let params: Vec<String> = serde_json::from_str(&params_json).unwrap_or_default();
+    if params.len() < expected {
+      short += 1;
+    }
+  }
+  Ok(short)
+}
+
+#[tauri::command]
+fn update_scheme(
+  db: State<'_, DbState>,
+  scheme_id: String,
+  patch: SchemeInput,
+) -> Result<UrlScheme, String> {
+  let name = patch.name.trim();
+  let icon = patch.icon.trim();
+  let template = patch.template.trim();
+  if name.is_empty() || template.is_empty() {
+    return Err("Scheme name and template are required".to_string());
+  }
+
+  let icon = if icon.is_empty() { "🔗".to_string() } else { icon.to_string() };
+  let kind = normalize_scheme_kind(patch.kind)?.to_db();
+  let param_type = normalize_param_type(&patch.param_type)?;
+
+  let conn = lock_db(&db)?;
+  let broken = count_bindings_short_of(&conn, &scheme_id, count_template_params(template))?;
+  if broken > 0 {
+    return Err(format!(
+      "{broken} task action(s) bound to this scheme would be missing parameters for the new template"
+    ));
+  }
+  let affected = conn
+    .execute(
+      "UPDATE schemes
+       SET name = ?2, icon = ?3, template = ?4, kind = ?5, param_type = ?6, enabled = COALESCE(?7, enabled)
+       WHERE id = ?1",
+      params![scheme_id, name, icon, template, kind, param_type, patch.enabled],
+    )
+    .map_err(|err| format!("Failed to update scheme: {err}"))?;
+
+  if affected == 0 {
+    return Err("Scheme not found".to_string());
+  }
+
+  mark_snapshot_dirty(&db);
+  fetch_scheme_by_id(&conn, &scheme_id)
+}
+
+#[tauri::command]
+fn set_scheme_enabled(db: State<'_, DbState>, scheme_id: String, enabled: bool) -> Result<UrlScheme, String> {
+  let conn = lock_db(&db)?;
+  let affected = conn
+    .execute(
+      "UPDATE schemes SET enabled = ?2 WHERE id = ?1",
+      params![scheme_id, enabled],
+    )
+    .map_err(|err| format!("Failed to update scheme: {err}"))?;
+
+  if affected == 0 {
+    return Err("Scheme not found".to_string());
+  }
+
+  mark_snapshot_dirty(&db);
+  fetch_scheme_by_id(&conn, &scheme_id)
+}
+
+/// Rewrites scheme positions to follow `ordered_ids`, like `reorder_lists`.
+#[tauri::command]
+fn reorder_schemes(db: State<'_, DbState>, ordered_ids: Vec<String>) -> Result<Vec<UrlScheme>, String> {
+  let mut conn = lock_db(&db)?;
+  let tx = conn
+    .transaction()
+    .map_err(|err| format!("Failed to start transaction: {err}"))?;
+
+  let current: Vec<String> = load_schemes(&tx, true)?.into_iter().map(|scheme| scheme.id).collect();
+  rewrite_positions(&tx, "schemes", &current, &ordered_ids)?;
+
+  tx
+    .commit()
+    .map_err(|err| format!("Failed to commit scheme reorder: {err}"))?;
+  mark_snapshot_dirty(&db);
+
+  load_schemes(&conn, true)
+}
+
+/// Refuses while any task still has an action bound to the scheme, unless `force`
+/// is set. Returns how many tasks lost a binding.
+#[tauri::command]
+fn delete_scheme(db: State<'_, DbState>, scheme_id: String, force: Option<bool>) -> Result<usize, String> {
+  let conn = lock_db(&db)?;
+  let bound_tasks: i64 = conn
+    .query_row(
+      "SELECT COUNT(DISTINCT task_id) FROM task_actions WHERE scheme_id = ?1",
+      params![scheme_id],
+      |row| row.get(0),
+    )
+    .map_err(|err| format!("Failed to count scheme bindings: {err}"))?;
+  if bound_tasks > 0 && !force.unwrap_or(false) {
+    return Err(format!(
+      "Scheme is bound to {bound_tasks} task(s); pass force to delete it and drop those bindings"
+    ));
+  }
+
+  // Bindings go with the scheme through the task_actions cascade.
+  conn
+    .execute("DELETE FROM schemes WHERE id = ?1", params![scheme_id])
+    .map_err(|err| format!("Failed to delete scheme: {err}"))?;
+
+  mark_snapshot_dirty(&db);
+  Ok(bound_tasks as usize)
+}
+
+/// Re-inserts any built-in scheme whose id is missing. Schemes that still
+/// exist are left untouched, even if the user edited them.
+#[tauri::command]
+fn restore_default_schemes(db: State<'_, DbState>) -> Result<Vec<UrlScheme>, String> {
+  let conn = lock_db(&db)?;
+  let mut stmt = conn
+    .prepare(
+      "INSERT OR IGNORE INTO schemes (id, name, icon, template, kind, param_type, position)
+       VALUES (?1, ?2, ?3, ?4, ?5, ?6, (SELECT COALESCE(MAX(position), -1) + 1 FROM schemes))",
+    )
+    .map_err(|err| format!("Failed to prepare scheme restore statement: {err}"))?;
+
+  let mut restored = Vec::new();
+  for scheme in default_schemes() {
+    let affected = stmt
+      .execute(params![
+        scheme.id,
+        scheme.name,
+        scheme.icon,
+        scheme.template,
+        scheme.kind.to_db(),
+        scheme.param_type
+      ])
+      .map_err(|err| format!("Failed to restore default scheme: {err}"))?;
+    if affected == 1 {
+      restored.push(scheme);
+    }
+  }
+
+  if !restored.is_empty() {
+    mark_snapshot_dirty(&db);
+  }
+  Ok(restored)
+}
+
+#[tauri::command]
+fn supported_schemes(db: State<'_, DbState>) -> Result<Vec<SchemeSupport>, String> {
+  let conn = lock_db(&db)?;
+  let platform = current_platform();
+  Ok(
+    load_schemes(&conn, true)?
+      .into_iter()
+      .map(|scheme| scheme_support(scheme, platform))
+      .collect(),
+  )
+}
+
+/// Tasks in the order of `ids`, skipping ids that don't exist.
+#[tauri::command]
+fn get_tasks_by_ids(db: State<'_, DbState>, ids: Vec<String>) -> Result<Vec<TaskItem>, String> {
+  if ids.is_empty() {
+    return Ok(Vec::new());
+  }
+
+  let conn = lock_db(&db)?;
+  let mut tasks = query_tasks_with_actions(
+    &conn,
+    &format!("WHERE t.id IN ({})", sql_placeholders(ids.len())),
+    params_from_iter(ids.iter()),
+    &HashMap::new(),
+  )?;
+  attach_task_relations(&conn, &mut tasks)?;
+  let mut found: HashMap<String, TaskItem> = tasks.into_iter().map(|task| (task.id.clone(), task)).collect();
+
+  Ok(ids.iter().filter_map(|id| found.remove(id)).collect())
+}
+
+/// Checks params for a scheme the way URL resolution will: there must be at least one
+/// per placeholder, and each that fills one must encode under the scheme's param type.
+/// Extra params are ignored when rendering, so they are never errors.
+#[tauri::command]
+fn validate_scheme_params(
+  db: State<'_, DbState>,
+  scheme_id: String,
+  params: Vec<String>,
+) -> Result<SchemeParamCheck, String> {
+  let conn = lock_db(&db)?;
+  let scheme = fetch_scheme_by_id(&conn, &scheme_id)?;
+
+  let expected = count_template_params(&scheme.template);
+  let count_error = (params.len() < expected).then(|| {
+    format!(
+      "{} needs {expected} parameter(s) but {} were given",
+      scheme.name,
+      params.len()
+    )
+  });
+  let param_errors: Vec<Option<String>> = params
+    .iter()
+    .enumerate()
+    .map(|(index, value)| {
+      if index < expected {
+        encode_action_param(&scheme, index, value).err()
+      } else {
+        None
+      }
+    })
+    .collect();
+
+  Ok(SchemeParamCheck {
+    ok: count_error.is_none() && param_errors.iter().all(Option::is_none),
+    count_error,
+    param_errors,
+  })
+}
+
+/// One of a task's actions resolved for its kind: a URL to open, text to copy,
+/// or a command line to run.
+fn resolve_task_action(conn: &Connection, task_id: &str, action_index: usize) -> Result<ActionTarget, String> {
+  let task = fetch_task_by_id(conn, task_id)?;
+  let action = task
+    .actions
+    .as_deref()
+    .unwrap_or_default()
+    .get(action_index)
+    .ok_or_else(|| "Action not found".to_string())?;
+  let scheme = fetch_scheme_by_id(conn, &action.scheme_id)?;
+  Ok(ActionTarget {
+    kind: scheme.kind,
+    value: render_action_url(&scheme, &action.params)?,
+  })
+}
+
+#[tauri::command]
+fn resolve_action_url(db: State<'_, DbState>, task_id: String, action_index: usize) -> Result<ActionTarget, String> {
+  let conn = lock_db(&db)?;
+  resolve_task_action(&conn, &task_id, action_index)
+}
+
+/// Runs a `shell` action through the platform shell (`sh -c`, or `cmd /C` on
+/// Windows) and waits for it. Refused unless `allow_shell_actions` is on.
+#[tauri::command]
+async fn run_shell_action(
+  app: AppHandle,
+  db: State<'_, DbState>,
+  task_id: String,
+  action_index: usize,
+) -> Result<ShellActionOutput, String> {
+  let command_line = {
+    let conn = lock_db(&db)?;
+    if !read_bool_setting(&conn, SETTING_ALLOW_SHELL_ACTIONS)? {
+      return Err("Shell actions are disabled; turn on allow_shell_actions to run them".to_string());
+    }
+    let target = resolve_task_action(&conn, &task_id, action_index)?;
+    if target.kind != SchemeKind::Shell {
+      return Err("Action is not a shell action".to_string());
+    }
+    target.value
+  };
+
+  let (program, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+  let output = app
+    .shell()
+    .command(program)
+    .args([flag, command_line.as_str()])
+    .output()
+    .await
+    .map_err(|err| format!("Failed to run shell action: {err}"))?;
+  Ok(ShellActionOutput {
+    exit_code: output.status.code(),
+    stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+    stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+  })
+}
+
+#[tauri::command]
+fn resolve_actions_for_tasks(
+  db: State<'_, DbState>,
+  task_ids: Vec<String>,
+) -> Result<HashMap<String, Vec<ResolvedAction>>, String> {
+  if task_ids.is_empty() {
+    return Ok(HashMap::new());
+  }
+
+  let conn = lock_db(&db)?;
+  let schemes: HashMap<String, UrlScheme> = load_schemes(&conn, true)?
+    .into_iter()
+    .map(|scheme| (scheme.id.clone(), scheme))
+    .collect();
+  let tasks = query_tasks(
+    &conn,
+    &format!("WHERE t.id IN ({})", sql_placeholders(task_ids.len())),
+    params_from_iter(task_ids.iter()),
+  )?;
+
+  Ok(
+    tasks
+      .iter()
+      .map(|task| (task.id.clone(), resolve_task_actions(task, &schemes)))
+      .collect(),
+  )
+}
+
+/// Reminder queue as it would look at `at_ms`, ignoring what has already fired.
+#[tauri::command]
+fn simulate_reminders(db: State<'_, DbState>, at_ms: i64) -> Result<Vec<DebugNextReminder>, String> {
+  let conn = lock_db(&db)?;
+  let mut candidates = collect_reminder_candidates(&conn, at_ms, true)?;
+  candidates.sort_by_key(|candidate| candidate.remind_at_ms);
+  Ok(
+    candidates
+      .into_iter()
+      .map(|candidate| debug_reminder(candidate, at_ms))
+      .collect(),
+  )
+}
+
+/// When a relative reminder would fire for the given inputs, without saving a task.
+#[tauri::command]
+fn compute_reminder_preview(
+  db: State<'_, DbState>,
+  due_date: String,
+  time: String,
+  offset_minutes: i64,
+) -> Result<Option<i64>, String> {
+  let conn = lock_db(&db)?;
+  let ambiguity = read_dst_ambiguity(&conn)?;
+  let task = TaskItem {
+    due_date: Some(due_date),
+    time: Some(time),
+    reminder: Some(Reminder {
+      reminder_type: "relative".to_string(),
+      offset_minutes,
+      ..Reminder::default()
+    }),
+    ..TaskItem::default()
+  };
+  Ok(compute_remind_at(&task, None, ambiguity))
+}
+
+fn load_unschedulable_reminders(conn: &Connection) -> Result<Vec<TaskItem>, String> {
+  let tasks = query_tasks(
+    conn,
+    "WHERE t.completed = 0
+       AND t.reminder = 1
+       AND NOT (
+         t.reminder_type = 'after_completion_of'
+         AND EXISTS (SELECT 1 FROM tasks d WHERE d.id = t.reminder_depends_on AND d.deleted_at IS NULL)
+       )
+     ORDER BY t.rowid ASC",
+    [],
+  )?;
+  let ambiguity = read_dst_ambiguity(conn)?;
+  Ok(
+    tasks
+      .into_iter()
+      .filter(|task| compute_remind_at(task, None, ambiguity).is_none())
+      .collect(),
+  )
+}
+
+/// Open tasks with a reminder enabled that can never fire as saved, e.g. missing a date
+/// or time. After-completion reminders only count once their dependency is gone.
+#[tauri::command]
+fn unschedulable_reminders(db: State<'_, DbState>) -> Result<Vec<TaskItem>, String> {
+  let conn = lock_db(&db)?;
+  load_unschedulable_reminders(&conn)
+}
+
+/// The highest-priority open task due soonest, undated tasks last, oldest first on ties.
+#[tauri::command]
+fn focus_task(db: State<'_, DbState>) -> Result<Option<TaskItem>, String> {
+  let conn = lock_db(&db)?;
+  let tasks = query_tasks(
+    &conn,
+    "WHERE t.completed = 0 ORDER BY t.created_at ASC, t.rowid ASC",
+    [],
+  )?;
+  Ok(tasks.into_iter().min_by_key(|task| {
+    let due = task_due_instant(task);
+    (std::cmp::Reverse(task.priority), due.is_none(), due)
+  }))
+}
+
+#[tauri::command]
+fn list_tasks_by_tag(db: State<'_, DbState>, tag: String) -> Result<Vec<TaskItem>, String> {
+  let conn = lock_db(&db)?;
+  query_tasks(
+    &conn,
+    &format!("WHERE {TAG_MATCH_CONDITION} {TASK_ORDER}"),
+    params![tag.trim()],
+  )
+}
+
+/// Substring search over title and detail. Matching is case-insensitive for ASCII only,
+/// as with SQLite's `LIKE`; `%` and `_` in the query match literally.
+#[tauri::command]
+fn search_tasks(db: State<'_, DbState>, query: String) -> Result<Vec<TaskItem>, String> {
+  let query = query.trim();
+  if query.is_empty() {
+    return Ok(Vec::new());
+  }
+
+  let escaped = query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+  let conn = lock_db(&db)?;
+  query_tasks(
+    &conn,
+    &format!("WHERE (t.title LIKE ?1 ESCAPE '\\' OR t.detail LIKE ?1 ESCAPE '\\') {TASK_ORDER}"),
+    params![format!("%{escaped}%")],
+  )
+}
+
+/// Date windows for `list_tasks_due`, relative to the local date.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum DueRange {
+  Today,
+  Overdue,
+  Upcoming,
+}
+
+/// Sort keys accepted by `list_tasks_sorted`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TaskSort {
+  Due,
+  Created,
+  Updated,
+  Priority,
+  Title,
+  Manual,
+}
+
+/// Maps a sort key onto an ORDER BY clause. Only these fixed column expressions
+/// ever reach the SQL; nothing from the caller is interpolated.
+fn task_sort_clause(sort: TaskSort, desc: bool) -> String {
+  let dir = if desc { "DESC" } else { "ASC" };
+  let keys = match sort {
+    // Undated tasks sort after dated ones in either direction.
+    TaskSort::Due => format!("t.date IS NULL, t.date {dir}, t.time IS NULL, t.time {dir}"),
+    TaskSort::Created => format!("t.created_at {dir}"),
+    TaskSort::Updated => format!("t.updated_at {dir}"),
+    TaskSort::Priority => format!("t.priority {dir}"),
+    TaskSort::Title => format!("t.title COLLATE NOCASE {dir}"),
+    TaskSort::Manual => format!("t.sort_order {dir}"),
+  };
+  format!("ORDER BY {keys}, t.rowid ASC")
+}
+
+/// Open repeating tasks with neither an end date nor a remaining count, so they
+/// keep spawning instances forever.
+#[tauri::command]
+fn unbounded_recurring_tasks(db: State<'_, DbState>) -> Result<Vec<TaskItem>, String> {
+  let conn = lock_db(&db)?;
+  query_tasks(
+    &conn,
+    &format!(
+      "WHERE t.completed = 0
+         AND t.repeat_type IS NOT NULL
+         AND t.repeat_until IS NULL
+         AND t.repeat_count IS NULL
+       {TASK_ORDER}"
+    ),
+    [],
+  )
+}
+
+/// Tasks in a caller-chosen order; `get_app_snapshot` remains the default-order view.
+#[tauri::command]
+fn list_tasks_sorted(
+  db: State<'_, DbState>,
+  sort: TaskSort,
+  desc: bool,
+  list_id: Option<String>,
+) -> Result<Vec<TaskItem>, String> {
+  let order = task_sort_clause(sort, desc);
+  let conn = lock_db(&db)?;
+  match list_id {
+    Some(list_id) if list_id == UNFILED_LIST_KEY => {
+      query_tasks(&conn, &format!("WHERE t.list_id IS NULL {order}"), [])
+    }
+    Some(list_id) => query_tasks(&conn, &format!("WHERE t.list_id = ?1 {order}"), params![list_id]),
+    None => query_tasks(&conn, &order, []),
+  }
+}
+
+/// Incomplete tasks due today, overdue (an earlier date, or today at a time that
+/// has passed), or upcoming (a later date), judged by the local clock as
+/// reminders are.
+#[tauri::command]
+fn list_tasks_due(db: State<'_, DbState>, range: DueRange) -> Result<Vec<TaskItem>, String> {
+  let now = Local::now();
+  let today = now.format("%Y-%m-%d").to_string();
+  let (condition, values) = match range {
+    DueRange::Today => ("t.date = ?1", vec![today]),
+    DueRange::Overdue => (
+      "(t.date < ?1 OR (t.date = ?1 AND t.time IS NOT NULL AND t.time < ?2))",
+      vec![today, now.format("%H:%M").to_string()],
+    ),
+    DueRange::Upcoming => ("t.date > ?1", vec![today]),
+  };
+
+  let conn = lock_db(&db)?;
+  query_tasks(
+    &conn,
+    &format!(
+      "WHERE t.completed = 0 AND t.date IS NOT NULL AND {condition}
+       ORDER BY t.date ASC, t.time IS NULL, t.time ASC, t.rowid ASC"
+    ),
+    params_from_iter(values),
+  )
+}
+
+/// One page of tasks in snapshot order, filtered and paged in SQL.
+#[tauri::command]
+fn list_tasks_paged(
+  db: State<'_, DbState>,
+  list_id: Option<String>,
+  offset: i64,
+  limit: i64,
+  include_completed: bool,
+) -> Result<Vec<TaskItem>, String> {
+  if offset < 0 || limit <= 0 {
+    return Err("Page offset must be non-negative and limit positive".to_string());
+  }
+
+  let mut conditions: Vec<&str> = Vec::new();
+  let mut values: Vec<SqlValue> = Vec::new();
+  match list_id {
+    Some(list_id) if list_id == UNFILED_LIST_KEY => conditions.push("t.list_id IS NULL"),
+    Some(list_id) => {
+      conditions.push("t.list_id = ?");
+      values.push(SqlValue::Text(list_id));
+    }
+    None => {}
+  }
+  if !include_completed {
+    conditions.push("t.completed = 0");
+  }
+  values.push(SqlValue::Integer(limit));
+  values.push(SqlValue::Integer(offset));
+
+  let where_clause = if conditions.is_empty() {
+    String::new()
+  } else {
+    format!("WHERE {}", conditions.join(" AND "))
+  };
+  let conn = lock_db(&db)?;
+  let mut tasks = query_tasks_with_actions(
+    &conn,
+    &format!("{where_clause} {TASK_ORDER} LIMIT ? OFFSET ?"),
+    params_from_iter(values),
+    &HashMap::new(),
+  )?;
+  attach_task_relations(&conn, &mut tasks)?;
+  Ok(tasks)
+}
+
+#[tauri::command]
+fn tasks_filtered(db: State<'_, DbState>, filter: TaskFilter) -> Result<Vec<TaskItem>, String> {
+  let mut conditions: Vec<&str> = Vec::new();
+  let mut values: Vec<SqlValue> = Vec::new();
+
+  match filter.list_id {
+    Some(list_id) if list_id == UNFILED_LIST_KEY => conditions.push("t.list_id IS NULL"),
+    Some(list_id) => {
+      conditions.push("t.list_id = ?");
+      values.push(SqlValue::Text(list_id));
+    }
+    None => {}
+  }
+  if let Some(completed) = filter.completed {
+    conditions.push("t.completed = ?");
+    values.push(SqlValue::Integer(completed as i64));
+  }
+  for (bound, condition) in [(filter.due_from, "t.date >= ?"), (filter.due_to, "t.date <= ?")] {
+    if let Some(date) = bound {
+      if parse_date_ymd(&date).is_none() {
+        return Err(format!("Invalid filter date: {date}"));
+      }
+      conditions.push(condition);
+      values.push(SqlValue::Text(date));
+    }
+  }
+
+  if let Some(tag) = filter.tag {
+    conditions.push(TAG_MATCH_CONDITION);
+    values.push(SqlValue::Text(tag.trim().to_string()));
+  }
+  if let Some(priority) = filter.priority_min {
+    conditions.push("t.priority >= ?");
+    values.push(SqlValue::Integer(priority.to_db()));
+  }
+
+  let where_clause = if conditions.is_empty() {
+    String::new()
+  } else {
+    format!("WHERE {}", conditions.join(" AND "))
+  };
+  let conn = lock_db(&db)?;
+  query_tasks(
+    &conn,
+    &format!("{where_clause} ORDER BY t.date IS NULL, t.date ASC, t.time ASC, t.rowid ASC"),
+    params_from_iter(values),
+  )
+}
+
+/// Share of tasks dated within `start..=end` (YYYY-MM-DD) that are done.
+#[tauri::command]
+fn completion_rate(db: State<'_, DbState>, start: String, end: String) -> Result<CompletionRate, String> {
+  let (Some(start_date), Some(end_date)) = (parse_date_ymd(&start), parse_date_ymd(&end)) else {
+    return Err("Period dates must be YYYY-MM-DD".to_string());
+  };
+  if start_date > end_date {
+    return Err("Period start must not be after its end".to_string());
+  }
+
+  let conn = lock_db(&db)?;
+  let (due, completed): (i64, i64) = conn
+    .query_row(
+      "SELECT COUNT(*), COALESCE(SUM(completed), 0)
+       FROM tasks
+       WHERE deleted_at IS NULL AND date >= ?1 AND date <= ?2",
+      params![start_date.format("%Y-%m-%d").to_string(), end_date.format("%Y-%m-%d").to_string()],
+      |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .map_err(|err| format!("Failed to compute completion rate: {err}"))?;
+
+  let rate = if due == 0 { 0.0 } else { completed as f64 / due as f64 };
+  Ok(CompletionRate { due, completed, rate })
+}
+
+#[tauri::command]
+fn storage_by_list(db: State<'_, DbState>) -> Result<Vec<ListStorage>, String> {
+  let conn = lock_db(&db)?;
+  let mut storage: Vec<ListStorage> = load_lists(&conn)?
+    .into_iter()
+    .map(|list| ListStorage {
+      list_id: list.id,
+      list_name: Some(list.name),
+      task_count: 0,
+      approx_bytes: 0,
+    })
+    .collect();
+  storage.push(ListStorage {
+    list_id: UNFILED_LIST_KEY.to_string(),
+    list_name: None,
+    task_count: 0,
+    approx_bytes: 0,
+  });
+
+  let mut stmt = conn
+    .prepare(
+      "SELECT l.id,
+              COUNT(*),
+              SUM(
+                LENGTH(CAST(t.title AS BLOB))
+                + COALESCE(LENGTH(CAST(t.detail AS BLOB)), 0)
+                + COALESCE((SELECT LENGTH(CAST(n.body AS BLOB)) FROM task_notes n WHERE n.task_id = t.id), 0)
+                + COALESCE(
+                    (SELECT SUM(LENGTH(CAST(a.params AS BLOB))) FROM task_actions a WHERE a.task_id = t.id),
+                    0
+                  )
+              )
+       FROM tasks t
+       LEFT JOIN lists l ON l.id = t.list_id
+       WHERE t.deleted_at IS NULL
+       GROUP BY l.id",
+    )
+    .map_err(|err| format!("Failed to query list storage: {err}"))?;
+  let rows = stmt
+    .query_map([], |row| {
+      Ok((
+        row.get::<_, Option<String>>(0)?,
+        row.get::<_, i64>(1)?,
+        row.get::<_, Option<i64>>(2)?.unwrap_or(0),
+      ))
+    })
+    .map_err(|err| format!("Failed to map list storage: {err}"))?;
+
+  for row in rows {
+    let (list_id, task_count, approx_bytes) =
+      row.map_err(|err| format!("Failed to read list storage row: {err}"))?;
+    // Tasks pointing at a missing list fall into the unfiled bucket with NULL ones.
+    let key = list_id.unwrap_or_else(|| UNFILED_LIST_KEY.to_string());
+    if let Some(entry) = storage.iter_mut().find(|entry| entry.list_id == key) {
+      entry.task_count += task_count;
+      entry.approx_bytes += approx_bytes;
+    }
+  }
+
+  storage.sort_by_key(|entry| std::cmp::Reverse(entry.approx_bytes));
+  Ok(storage)
+}
+
+/// Validates and normalizes a new-task payload into a fresh, uncompleted task.
+fn new_task_from_input(input: NewTaskInput) -> Result<TaskItem, String> {
+  let repeat_rule = normalize_repeat_rule(&input.repeat_rule)?;
+  let reminder = normalize_reminder(&input.reminder)?;
+
+  let title = input.title.trim().to_string();
+  if title.is_empty() {
+    return Err("Task title is required".to_string());
+  }
+
+  Ok(TaskItem {
+    id: format!("task_{}", Uuid::new_v4()),
+    list_id: input.list_id,
+    title,
+    detail: normalize_detail(input.detail),
+    completed: false,
+    completed_at: None,
+    due_date: input.due_date,
+    time: input.time,
+    reminder,
+    repeat_rule,
+    actions: input.actions,
+    priority: input.priority.unwrap_or_default(),
+    tags: normalize_tags(&input.tags.unwrap_or_default()),
+    timezone: input.timezone.as_deref().map(normalize_timezone).transpose()?.flatten(),
+    color: normalize_hex_color(input.color.as_deref())?,
+    created_at: None,
+    updated_at: None,
+  })
+}
+
+fn template_from_row(row: &rusqlite::Row) -> rusqlite::Result<TaskTemplate> {
+  let reminder: Option<String> = row.get(5)?;
+  let repeat: Option<String> = row.get(6)?;
+  let actions: String = row.get(7)?;
+  Ok(TaskTemplate {
+    id: row.get(0)?,
+    name: row.get(1)?,
+    list_id: row.get(2)?,
+    title: row.get(3)?,
+    detail: row.get(4)?,
+    reminder: reminder.and_then(|text| serde_json::from_str(&text).ok()),
+    repeat_rule: repeat.and_then(|text| serde_json::from_str(&text).ok()),
+    actions: serde_json::from_str(&actions).unwrap_or_default(),
+    created_at: row.get(8)?,
+  })
+}
+
+#[tauri::command]
+fn list_templates(db: State<'_, DbState>) -> Result<Vec<TaskTemplate>, String> {
+  let conn = lock_db(&db)?;
+  let mut stmt = conn
+    .prepare(
+      "SELECT id, name, list_id, title, detail, reminder, repeat, actions, created_at
+       FROM task_templates
+       ORDER BY name ASC, rowid ASC",
+    )
+    .map_err(|err| format!("Failed to query templates: {err}"))?;
+  let rows = stmt
+    .query_map([], template_from_row)
+    .map_err(|err| format!("Failed to map templates: {err}"))?;
+
+  let mut templates = Vec::new();
+  for row in rows {
+    templates.push(row.map_err(|err| format!("Failed to read template row: {err}"))?);
+  }
+  Ok(templates)
+}
+
+/// Saves a task's title, detail, list, reminder, repeat rule and actions as a template;
+/// the date and time are left out.
+#[tauri::command]
+fn task_to_template(db: State<'_, DbState>, task_id: String, name: String) -> Result<TaskTemplate, String> {
+  let name = name.trim();
+  if name.is_empty() {
+    return Err("Template name is required".to_string());
+  }
+
+  let conn = lock_db(&db)?;
+  let task = fetch_task_by_id(&conn, &task_id)?;
+  let template = TaskTemplate {
+    id: format!("template_{}", Uuid::new_v4()),
+    name: name.to_string(),
+    list_id: task.list_id,
+    title: task.title,
+    detail: task.detail,
+    reminder: task.reminder,
+    repeat_rule: task.repeat_rule,
+    actions: task.actions.unwrap_or_default(),
+    created_at: now_epoch_ms(),
+  };
+
+  let encode = |err: serde_json::Error| format!("Failed to encode template: {err}");
+  conn
+    .execute(
+      "INSERT INTO task_templates (id, name, list_id, title, detail, reminder, repeat, actions, created_at)
+       VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+      params![
+        template.id,
+        template.name,
+        template.list_id,
+        template.title,
+        template.detail,
+        template.reminder.as_ref().map(serde_json::to_string).transpose().map_err(encode)?,
+        template.repeat_rule.as_ref().map(serde_json::to_string).transpose().map_err(encode)?,
+        serde_json::to_string(&template.actions).map_err(encode)?,
+        template.created_at
+      ],
+    )
+    .map_err(|err| format!("Failed to create template: {err}"))?;
+
+  Ok(template)
+}
+
+#[tauri::command]
+fn create_task(
+  db: State<'_, DbState>,
+  scheduler: State<'_, SchedulerState>,
+  input: NewTaskInput,
+) -> Result<TaskItem, String> {
+  let skip_default_reminder = input.skip_default_reminder;
+  let mut task = new_task_from_input(input)?;
+
+  let mut conn = lock_db(&db)?;
+  let tx = conn
+    .transaction()
+    .map_err(|err| format!("Failed to start transaction: {err}"))?;
+
+  if !skip_default_reminder {
+    apply_list_default_reminder(&tx, &mut task)?;
+  }
+  validate_task_schedule(&task)?;
+  validate_reminder_dependency(&tx, &task)?;
+  validate_task_actions(&tx, &task)?;
+  insert_task_row(&tx, &task)?;
+  record_task_change(&tx, &task.id, "created", None, None, None)?;
+
+  tx
+    .commit()
+    .map_err(|err| format!("Failed to commit task creation: {err}"))?;
+  scheduler_wakeup(&scheduler);
+  mark_snapshot_dirty(&db);
+
+  fetch_task_by_id(&conn, &task.id)
+}
+
+/// Files a task under another list (or unfiles it with `None`) without touching
+/// anything else on the task.
+#[tauri::command]
+fn move_task(
+  db: State<'_, DbState>,
+  task_id: String,
+  list_id: Option<String>,
+) -> Result<TaskItem, String> {
+  let conn = lock_db(&db)?;
+  let task = fetch_task_by_id(&conn, &task_id)?;
+  if let Some(list_id) = list_id.as_deref() {
+    ensure_list_exists(&conn, list_id)?;
+  }
+  if task.list_id == list_id {
+    return Ok(task);
+  }
+
+  conn
+    .execute(
+      "UPDATE tasks SET list_id = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+      params![task_id, list_id],
+    )
+    .map_err(|err| format!("Failed to move task: {err}"))?;
+  record_task_change(
+    &conn,
+    &task_id,
+    "updated",
+    Some("listId"),
+    task.list_id.clone(),
+    list_id.clone(),
+  )?;
+
+  // The list only feeds the notification text, which is read at fire time, so the
+  // scheduler has nothing to recompute.
+  mark_snapshot_dirty(&db);
+  Ok(TaskItem { list_id, ..task })
+}
+
+/// Changes only a task's time; `None` clears it. Legacy forms like `9:30 PM` are
+/// accepted and stored as `HH:MM`. Fired reminders are keyed by instant, so the
+/// rescheduled reminder fires again at its new time.
+#[tauri::command]
+fn set_task_time(
+  db: State<'_, DbState>,
+  scheduler: State<'_, SchedulerState>,
+  task_id: String,
+  time: Option<String>,
+) -> Result<TaskItem, String> {
+  let time = match time.as_deref().map(str::trim).filter(|value| !value.is_empty()) {
+    Some(value) => Some(
+      parse_time_flexible(value)
+        .ok_or_else(|| format!("Invalid time: {value}"))?
+        .format("%H:%M")
+        .to_string(),
+    ),
+    None => None,
+  };
+
+  let conn = lock_db(&db)?;
+  let task = fetch_task_by_id(&conn, &task_id)?;
+  if task.time == time {
+    return Ok(task);
+  }
+  validate_task_schedule(&TaskItem {
+    time: time.clone(),
+    ..task.clone()
+  })?;
+
+  conn
+    .execute(
+      "UPDATE tasks SET time = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+      params![task_id, time],
+    )
+    .map_err(|err| format!("Failed to update task time: {err}"))?;
+  delete_fired_reminders_for_task(&conn, &task_id)?;
+  record_task_change(&conn, &task_id, "updated", Some("time"), task.time.clone(), time.clone())?;
+
+  scheduler_wakeup(&scheduler);
+  mark_snapshot_dirty(&db);
+  Ok(TaskItem { time, ..task })
+}
+
+/// Moves a task to `new_index` within its list's manual order (`None` is the unfiled
+/// bucket), shifting the tasks in between. Indexes past the end move it last.
+#[tauri::command]
+fn move_task_position(
+  db: State<'_, DbState>,
+  list_id: Option<String>,
+  task_id: String,
+  new_index: usize,
+) -> Result<(), String> {
+  let mut conn = lock_db(&db)?;
+  let tx = conn
+    .transaction()
+    .map_err(|err| format!("Failed to start transaction: {err}"))?;
+
+  let mut order = {
+    let mut stmt = tx
+      .prepare(
+        "SELECT id FROM tasks
+         WHERE list_id IS ?1 AND deleted_at IS NULL
+         ORDER BY sort_order ASC, rowid ASC",
+      )
+      .map_err(|err| format!("Failed to query task order: {err}"))?;
+    let rows = stmt
+      .query_map(params![list_id], |row| row.get::<_, String>(0))
+      .map_err(|err| format!("Failed to map task order: {err}"))?;
+    rows
+      .collect::<Result<Vec<_>, _>>()
+      .map_err(|err| format!("Failed to read task order: {err}"))?
+  };
+
+  let current_index = order
+    .iter()
+    .position(|id| *id == task_id)
+    .ok_or_else(|| "Task not found in that list".to_string())?;
+  let moved = order.remove(current_index);
+  order.insert(new_index.min(order.len()), moved);
+
+  for (position, id) in order.iter().enumerate() {
+    tx
+      .execute(
+        "UPDATE tasks SET sort_order = ?2 WHERE id = ?1 AND sort_order <> ?2",
+        params![id, position as i64],
+      )
+      .map_err(|err| format!("Failed to update task position: {err}"))?;
+  }
+
+  tx
+    .commit()
+    .map_err(|err| format!("Failed to commit task move: {err}"))?;
+  mark_snapshot_dirty(&db);
+  Ok(())
+}
+
+/// Files every unfiled task (e.g. orphans left by a list deletion) under `target_list_id`.
+/// Returns how many tasks moved.
+#[tauri::command]
+fn file_unfiled_tasks(db: State<'_, DbState>, target_list_id: String) -> Result<usize, String> {
+  let conn = lock_db(&db)?;
+  ensure_list_exists(&conn, &target_list_id)?;
+  let moved = conn
+    .execute(
+      "UPDATE tasks SET list_id = ?1, updated_at = CURRENT_TIMESTAMP
+       WHERE list_id IS NULL AND deleted_at IS NULL",
+      params![target_list_id],
+    )
+    .map_err(|err| format!("Failed to file unfiled tasks: {err}"))?;
+
+  if moved > 0 {
+    mark_snapshot_dirty(&db);
+  }
+  Ok(moved)
+}
+
+#[tauri::command]
+fn save_task(
+  db: State<'_, DbState>,
+  scheduler: State<'_, SchedulerState>,
+  task: SaveTaskInput,
+) -> Result<TaskItem, String> {
+  let mut conn = lock_db(&db)?;
+  let tx = conn
+    .transaction()
+    .map_err(|err| format!("Failed to start transaction: {err}"))?;
+  let task_id = apply_task_save(&tx, task)?;
+
+  tx
+    .commit()
+    .map_err(|err| format!("Failed to commit task update: {err}"))?;
+  scheduler_wakeup(&scheduler);
+  mark_snapshot_dirty(&db);
+
+  fetch_task_by_id(&conn, &task_id)
+}
+
+/// Validates and writes a full task edit, recording its history; returns the task id.
+fn apply_task_save(tx: &Connection, task: SaveTaskInput) -> Result<String, String> {
+  let repeat_rule = normalize_repeat_rule(&task.repeat_rule)?;
+  let reminder = normalize_reminder(&task.reminder)?;
+
+  let title = task.title.trim().to_string();
+  if title.is_empty() {
+    return Err("Task title is required".to_string());
+  }
+
+  let before = fetch_task_by_id(tx, &task.id)?;
+
+  let completed_at = if task.completed == before.completed {
+    before.completed_at
+  } else if task.completed {
+    Some(now_epoch_ms())
+  } else {
+    None
+  };
+  let updated = TaskItem {
+    id: task.id,
+    list_id: task.list_id,
+    title,
+    detail: normalize_detail(task.detail),
+    completed: task.completed,
+    completed_at,
+    due_date: task.due_date,
+    time: task.time,
+    reminder,
+    repeat_rule,
+    actions: task.actions,
+    priority: task.priority.unwrap_or(before.priority),
+    tags: task.tags.map(|tags| normalize_tags(&tags)).unwrap_or_else(|| before.tags.clone()),
+    timezone: match task.timezone.as_deref() {
+      Some(value) => normalize_timezone(value)?,
+      None => before.timezone.clone(),
+    },
+    color: match task.color.as_deref() {
+      Some(value) => normalize_hex_color(Some(value))?,
+      None => before.color.clone(),
+    },
+    created_at: before.created_at.clone(),
+    updated_at: before.updated_at.clone(),
+  };
+
+  validate_task_schedule(&updated)?;
+  validate_reminder_dependency(tx, &updated)?;
+  validate_task_actions(tx, &updated)?;
+  if reminder_schedule_changed(&before, &updated) {
+    delete_fired_reminders_for_task(tx, &updated.id)?;
+  }
+  if update_task_row(tx, &updated)? == 0 {
+    return Err("Task not found".to_string());
+  }
+
+  let after = fetch_task_by_id(tx, &updated.id)?;
+  record_task_edits(tx, &before, &after)?;
+  Ok(updated.id)
+}
+
+fn set_task_completion(conn: &Connection, task_id: &str, completing: bool) -> Result<(), String> {
+  conn
+    .execute(
+      "UPDATE tasks SET completed = ?2, completed_at = ?3, updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+      params![task_id, if completing { 1 } else { 0 }, completing.then(now_epoch_ms)],
+    )
+    .map_err(|err| format!("Failed to toggle task completion: {err}"))?;
+  let change_type = if completing { "completed" } else { "reopened" };
+  record_task_change(conn, task_id, change_type, None, None, None)
+}
+
+fn next_recurrence_date(task: &TaskItem, catch_up: bool) -> Option<String> {
+  if catch_up {
+    compute_next_repeat_date_from(task, Local::now().date_naive())
+  } else {
+    compute_next_repeat_date(task)
+  }
+}
+
+/// Inserts the next instance of a repeating task, copying everything but id and state
+/// and counting down a bounded rule's remaining occurrences. Returns the new task's id.
+fn spawn_recurrence(conn: &Connection, task: &TaskItem, next_date: String) -> Result<String, String> {
+  let repeat_rule = task.repeat_rule.clone().map(|rule| RepeatRule {
+    count: rule.count.map(|count| count.saturating_sub(1).max(1)),
+    ..rule
+  });
+  let next_task = TaskItem {
+    id: format!("task_{}", Uuid::new_v4()),
+    completed: false,
+    completed_at: None,
+    due_date: Some(next_date),
+    repeat_rule,
+    created_at: None,
+    updated_at: None,
+    ..task.clone()
+  };
+  insert_task_row(conn, &next_task)?;
+  record_task_change(
+    conn,
+    &next_task.id,
+    "created",
+    None,
+    None,
+    Some(format!("recurrence of {}", task.id)),
+  )?;
+  Ok(next_task.id)
+}
+
+#[tauri::command]
+fn toggle_task_completed(
+  db: State<'_, DbState>,
+  scheduler: State<'_, SchedulerState>,
+  task_id: String,
+) -> Result<ToggleTaskResult, String> {
+  let mut conn = lock_db(&db)?;
+  let task = fetch_task_by_id(&conn, &task_id)?;
+  let completing = !task.completed;
+
+  let tx = conn
+    .transaction()
+    .map_err(|err| format!("Failed to start transaction: {err}"))?;
+
+  set_task_completion(&tx, &task_id, completing)?;
+
+  let mut spawned_id = None;
+  if completing {
+    let catch_up = read_bool_setting(&tx, SETTING_REPEAT_CATCH_UP)?;
+    if let Some(next_date) = next_recurrence_date(&task, catch_up) {
+      spawned_id = Some(spawn_recurrence(&tx, &task, next_date)?);
+    }
+  }
+
+  tx
+    .commit()
+    .map_err(|err| format!("Failed to commit task toggle: {err}"))?;
+  scheduler_wakeup(&scheduler);
+  mark_snapshot_dirty(&db);
+
+  Ok(ToggleTaskResult {
+    task: fetch_task_by_id(&conn, &task_id)?,
+    spawned: spawned_id
+      .map(|spawned_id| fetch_task_by_id(&conn, &spawned_id))
+      .transpose()?,
+  })
+}
+
+/// Toggles every given task. A series is the set of repeating tasks sharing a list,
+/// title and repeat rule (ignoring the remaining count); spawned instances copy all three, so `Consolidated` keeps
+/// the series intact while replacing a backlog of overdue instances with one.
+#[tauri::command]
+fn bulk_toggle_completed(
+  db: State<'_, DbState>,
+  scheduler: State<'_, SchedulerState>,
+  task_ids: Vec<String>,
+  recurrence: Option<RecurrenceSpawn>,
+) -> Result<Vec<TaskItem>, String> {
+  if task_ids.is_empty() {
+    return Ok(Vec::new());
+  }
+
+  let mut conn = lock_db(&db)?;
+  let id_clause = format!("WHERE t.id IN ({})", sql_placeholders(task_ids.len()));
+  let tasks = query_tasks(&conn, &id_clause, params_from_iter(task_ids.iter()))?;
+  let recurrence = recurrence.unwrap_or_default();
+
+  let tx = conn
+    .transaction()
+    .map_err(|err| format!("Failed to start transaction: {err}"))?;
+  let catch_up = read_bool_setting(&tx, SETTING_REPEAT_CATCH_UP)?;
+
+  let mut series: HashMap<(Option<String>, String, RepeatRule), &TaskItem> = HashMap::new();
+  for task in &tasks {
+    let completing = !task.completed;
+    set_task_completion(&tx, &task.id, completing)?;
+    let Some(rule) = task.repeat_rule.as_ref().filter(|_| completing) else {
+      continue;
+    };
+
+    match recurrence {
+      RecurrenceSpawn::Each => {
+        if let Some(next_date) = next_recurrence_date(task, catch_up) {
+          spawn_recurrence(&tx, task, next_date)?;
+        }
+      }
+      RecurrenceSpawn::Skip => {}
+      RecurrenceSpawn::Consolidated => {
+        let latest = series
+          .entry((task.list_id.clone(), task.title.clone(), RepeatRule { count: None, ..rule.clone() }))
+          .or_insert(task);
+        if task.due_date > latest.due_date {
+          *latest = task;
+        }
+      }
+    }
+  }
+
+  let today = Local::now().date_naive();
+  for task in series.into_values() {
+    if let Some(next_date) = compute_next_repeat_date_from(task, today) {
+      spawn_recurrence(&tx, task, next_date)?;
+    }
+  }
+
+  tx
+    .commit()
+    .map_err(|err| format!("Failed to commit bulk toggle: {err}"))?;
   scheduler_wakeup(&scheduler);
+  mark_snapshot_dirty(&db);
+
+  query_tasks(&conn, &id_clause, params_from_iter(task_ids.iter()))
+}
+
+/// Marks every given open task completed in one transaction, spawning the next
+/// occurrence of each repeating one. Returns the ids that were actually completed.
+#[tauri::command]
+fn complete_tasks(
+  db: State<'_, DbState>,
+  scheduler: State<'_, SchedulerState>,
+  ids: Vec<String>,
+) -> Result<Vec<String>, String> {
+  if ids.is_empty() {
+    return Ok(Vec::new());
+  }
 
-  let conn = open_connection(&db.db_path)?;
-  Ok(AppSnapshot {
-    lists: load_lists(&conn)?,
-    tasks: load_tasks(&conn)?,
-    schemes: load_schemes(&conn)?,
-  })
+  let mut conn = lock_db(&db)?;
+  let tasks = query_tasks(
+    &conn,
+    &format!("WHERE t.id IN ({}) AND t.completed = 0", sql_placeholders(ids.len())),
+    params_from_iter(ids.iter()),
+  )?;
+
+  let tx = conn
+    .transaction()
+    .map_err(|err| format!("Failed to start transaction: {err}"))?;
+  let catch_up = read_bool_setting(&tx, SETTING_REPEAT_CATCH_UP)?;
+  for task in &tasks {
+    set_task_completion(&tx, &task.id, true)?;
+    if let Some(next_date) = next_recurrence_date(task, catch_up) {
+      spawn_recurrence(&tx, task, next_date)?;
+    }
+  }
+  tx
+    .commit()
+    .map_err(|err| format!("Failed to commit task completion: {err}"))?;
+
+  if !tasks.is_empty() {
+    scheduler_wakeup(&scheduler);
+    mark_snapshot_dirty(&db);
+  }
+  Ok(tasks.into_iter().map(|task| task.id).collect())
 }
 
+/// Moves every given task to the trash in one transaction, like `delete_task`.
+/// Returns the ids that were actually deleted.
 #[tauri::command]
-fn debug_next_reminder(db: State<'_, DbState>) -> Result<Option<DebugNextReminder>, String> {
+fn delete_tasks(
+  db: State<'_, DbState>,
+  scheduler: State<'_, SchedulerState>,
+  ids: Vec<String>,
+) -> Result<Vec<String>, String> {
+  let mut conn = lock_db(&db)?;
+  let tx = conn
+    .transaction()
+    .map_err(|err| format!("Failed to start transaction: {err}"))?;
+
   let now = now_epoch_ms();
-  let next = query_next_reminder(&db.db_path, now)?;
-  Ok(next.map(|item| DebugNextReminder {
-    task_id: item.task_id,
-    task_title: item.task_title,
-    remind_at: item.remind_at_ms,
-    due_date: item.due_date,
-    time: item.time,
-    now,
-    delay_ms: item.remind_at_ms.saturating_sub(now),
-  }))
+  let mut deleted = Vec::new();
+  for id in ids {
+    let affected = tx
+      .execute(
+        "UPDATE tasks SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+        params![id, now],
+      )
+      .map_err(|err| format!("Failed to delete task: {err}"))?;
+    if affected > 0 {
+      record_task_change(&tx, &id, "deleted", None, None, None)?;
+      deleted.push(id);
+    }
+  }
+  tx
+    .commit()
+    .map_err(|err| format!("Failed to commit task deletion: {err}"))?;
+
+  if !deleted.is_empty() {
+    scheduler_wakeup(&scheduler);
+    mark_snapshot_dirty(&db);
+  }
+  Ok(deleted)
 }
 
+/// Imports tasks in one transaction, resolving title+date matches per `on_conflict`.
+/// A replaced task keeps its id and completion state.
 #[tauri::command]
-fn create_list(db: State<'_, DbState>, input: ListInput) -> Result<ListItem, String> {
-  let name = input.name.trim();
-  let icon = input.icon.trim();
-  if name.is_empty() {
-    return Err("List name is required".to_string());
-  }
+fn import_tasks_resolved(
+  db: State<'_, DbState>,
+  scheduler: State<'_, SchedulerState>,
+  inputs: Vec<NewTaskInput>,
+  on_conflict: ImportConflict,
+) -> Result<ImportReport, String> {
+  let mut conn = lock_db(&db)?;
+  let tx = conn
+    .transaction()
+    .map_err(|err| format!("Failed to start transaction: {err}"))?;
 
-  let list = ListItem {
-    id: format!("list_{}", Uuid::new_v4()),
-    name: name.to_string(),
-    icon: if icon.is_empty() { "🗂️".to_string() } else { icon.to_string() },
-  };
+  let mut report = ImportReport::default();
+  for input in inputs {
+    let task = new_task_from_input(input)?;
+    validate_task_schedule(&task)?;
+    validate_reminder_dependency(&tx, &task)?;
+    validate_task_actions(&tx, &task)?;
+
+    let existing = match on_conflict {
+      ImportConflict::Duplicate => None,
+      ImportConflict::Skip | ImportConflict::Replace => query_tasks(
+        &tx,
+        "WHERE t.title = ?1 AND t.date IS ?2 ORDER BY t.rowid ASC LIMIT 1",
+        params![task.title, task.due_date],
+      )?
+      .into_iter()
+      .next(),
+    };
 
-  let conn = open_connection(&db.db_path)?;
-  conn
-    .execute(
-      "INSERT INTO lists (id, name, icon) VALUES (?1, ?2, ?3)",
-      params![list.id, list.name, list.icon],
-    )
-    .map_err(|err| format!("Failed to create list: {err}"))?;
+    match (existing, on_conflict) {
+      (Some(_), ImportConflict::Skip) => report.skipped += 1,
+      (Some(before), _) => {
+        let updated = TaskItem {
+          id: before.id.clone(),
+          completed: before.completed,
+          completed_at: before.completed_at,
+          ..task
+        };
+        update_task_row(&tx, &updated)?;
+        let after = fetch_task_by_id(&tx, &updated.id)?;
+        record_task_edits(&tx, &before, &after)?;
+        report.replaced += 1;
+      }
+      (None, _) => {
+        insert_task_row(&tx, &task)?;
+        record_task_change(&tx, &task.id, "created", None, None, None)?;
+        report.created += 1;
+      }
+    }
+  }
 
-  Ok(list)
+  tx
+    .commit()
+    .map_err(|err| format!("Failed to commit task import: {err}"))?;
+  scheduler_wakeup(&scheduler);
+  mark_snapshot_dirty(&db);
+  Ok(report)
 }
 
+/// Rewrites every task time that only the flexible parser can read to canonical `HH:MM`.
+/// Returns how many were fixed; times that still don't parse are logged and left alone.
 #[tauri::command]
-fn update_list(db: State<'_, DbState>, list_id: String, patch: ListInput) -> Result<ListItem, String> {
-  let name = patch.name.trim();
-  let icon = patch.icon.trim();
-  if name.is_empty() {
-    return Err("List name is required".to_string());
-  }
+fn normalize_all_times(db: State<'_, DbState>, scheduler: State<'_, SchedulerState>) -> Result<usize, String> {
+  let mut conn = lock_db(&db)?;
+  let tx = conn
+    .transaction()
+    .map_err(|err| format!("Failed to start transaction: {err}"))?;
 
-  let list = ListItem {
-    id: list_id.clone(),
-    name: name.to_string(),
-    icon: if icon.is_empty() { "🗂️".to_string() } else { icon.to_string() },
+  let times = {
+    let mut stmt = tx
+      .prepare("SELECT id, time FROM tasks WHERE time IS NOT NULL AND time <> ''")
+      .map_err(|err| format!("Failed to query task times: {err}"))?;
+    let rows = stmt
+      .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+      .map_err(|err| format!("Failed to map task times: {err}"))?;
+    let mut times = Vec::new();
+    for row in rows {
+      times.push(row.map_err(|err| format!("Failed to read task time row: {err}"))?);
+    }
+    times
   };
 
-  let conn = open_connection(&db.db_path)?;
-  let affected = conn
-    .execute(
-      "UPDATE lists SET name = ?2, icon = ?3 WHERE id = ?1",
-      params![list.id, list.name, list.icon],
-    )
-    .map_err(|err| format!("Failed to update list: {err}"))?;
-
-  if affected == 0 {
-    return Err("List not found".to_string());
+  let mut fixed = 0;
+  for (task_id, time) in times {
+    if parse_time_hm(&time).is_some() {
+      continue;
+    }
+    let Some(parsed) = parse_time_flexible(&time) else {
+      eprintln!("normalize_all_times: cannot parse time {time:?} on task {task_id}");
+      continue;
+    };
+    let canonical = parsed.format("%H:%M").to_string();
+    tx
+      .execute(
+        "UPDATE tasks SET time = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+        params![task_id, canonical],
+      )
+      .map_err(|err| format!("Failed to normalize task time: {err}"))?;
+    record_task_change(&tx, &task_id, "updated", Some("time"), Some(time), Some(canonical))?;
+    fixed += 1;
   }
 
-  Ok(list)
+  tx
+    .commit()
+    .map_err(|err| format!("Failed to commit time normalization: {err}"))?;
+  if fixed > 0 {
+    scheduler_wakeup(&scheduler);
+    mark_snapshot_dirty(&db);
+  }
+  Ok(fixed)
 }
 
-#[tauri::command]
-fn create_scheme(db: State<'_, DbState>, input: SchemeInput) -> Result<UrlScheme, String> {
-  let name = input.name.trim();
-  let icon = input.icon.trim();
-  let template = input.template.trim();
-  if name.is_empty() || template.is_empty() {
-    return Err("Scheme name and template are required".to_string());
+fn ensure_list_exists(conn: &Connection, list_id: &str) -> Result<(), String> {
+  let exists: i64 = conn
+    .query_row(
+      "SELECT EXISTS(SELECT 1 FROM lists WHERE id = ?1)",
+      params![list_id],
+      |row| row.get(0),
+    )
+    .map_err(|err| format!("Failed to look up list: {err}"))?;
+  if exists == 0 {
+    return Err("List not found".to_string());
   }
+  Ok(())
+}
 
-  let scheme = UrlScheme {
-    id: format!("scheme_{}", Uuid::new_v4()),
-    name: name.to_string(),
-    icon: if icon.is_empty() { "🔗".to_string() } else { icon.to_string() },
-    template: template.to_string(),
-    kind: normalize_scheme_kind(input.kind),
-    param_type: match input.param_type.trim() {
-      "number" => "number".to_string(),
-      _ => "string".to_string(),
-    },
-  };
-
-  let conn = open_connection(&db.db_path)?;
+fn write_task_priority(conn: &Connection, task: &TaskItem, priority: Priority) -> Result<(), String> {
+  if task.priority == priority {
+    return Ok(());
+  }
   conn
     .execute(
-      "INSERT INTO schemes (id, name, icon, template, kind, param_type) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-      params![
-        scheme.id,
-        scheme.name,
-        scheme.icon,
-        scheme.template,
-        scheme.kind,
-        scheme.param_type
-      ],
+      "UPDATE tasks SET priority = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+      params![task.id, priority.to_db()],
     )
-    .map_err(|err| format!("Failed to create scheme: {err}"))?;
-
-  Ok(scheme)
+    .map_err(|err| format!("Failed to update task priority: {err}"))?;
+  record_task_change(
+    conn,
+    &task.id,
+    "updated",
+    Some("priority"),
+    encode_change_value(&Some(task.priority)),
+    encode_change_value(&Some(priority)),
+  )
 }
 
+/// Sets the priority level (0 = low, 1 = normal, 2 = high) on every given task that
+/// exists, returning how many were found.
 #[tauri::command]
-fn update_scheme(
-  db: State<'_, DbState>,
-  scheme_id: String,
-  patch: SchemeInput,
-) -> Result<UrlScheme, String> {
-  let name = patch.name.trim();
-  let icon = patch.icon.trim();
-  let template = patch.template.trim();
-  if name.is_empty() || template.is_empty() {
-    return Err("Scheme name and template are required".to_string());
+fn set_tasks_priority(db: State<'_, DbState>, task_ids: Vec<String>, priority: u8) -> Result<usize, String> {
+  let priority = Priority::from_level(priority as i64)?;
+  if task_ids.is_empty() {
+    return Ok(0);
   }
 
-  let scheme = UrlScheme {
-    id: scheme_id.clone(),
-    name: name.to_string(),
-    icon: if icon.is_empty() { "🔗".to_string() } else { icon.to_string() },
-    template: template.to_string(),
-    kind: normalize_scheme_kind(patch.kind),
-    param_type: match patch.param_type.trim() {
-      "number" => "number".to_string(),
-      _ => "string".to_string(),
-    },
-  };
-
-  let conn = open_connection(&db.db_path)?;
-  let affected = conn
-    .execute(
-      "UPDATE schemes SET name = ?2, icon = ?3, template = ?4, kind = ?5, param_type = ?6 WHERE id = ?1",
-      params![
-        scheme.id,
-        scheme.name,
-        scheme.icon,
-        scheme.template,
-        scheme.kind,
-        scheme.param_type
-      ],
-    )
-    .map_err(|err| format!("Failed to update scheme: {err}"))?;
-
-  if affected == 0 {
-    return Err("Scheme not found".to_string());
+  let mut conn = lock_db(&db)?;
+  let tx = conn
+    .transaction()
+    .map_err(|err| format!("Failed to start transaction: {err}"))?;
+  let tasks = query_tasks(
+    &tx,
+    &format!("WHERE t.id IN ({})", sql_placeholders(task_ids.len())),
+    params_from_iter(task_ids.iter()),
+  )?;
+  for task in &tasks {
+    write_task_priority(&tx, task, priority)?;
   }
 
-  Ok(scheme)
+  tx
+    .commit()
+    .map_err(|err| format!("Failed to commit priority update: {err}"))?;
+  mark_snapshot_dirty(&db);
+  Ok(tasks.len())
 }
 
+/// Moves a task's priority by `delta` levels, clamped to low..high.
 #[tauri::command]
-fn delete_scheme(db: State<'_, DbState>, scheme_id: String) -> Result<(), String> {
-  let conn = open_connection(&db.db_path)?;
-  conn
-    .execute("DELETE FROM schemes WHERE id = ?1", params![scheme_id])
-    .map_err(|err| format!("Failed to delete scheme: {err}"))?;
-
-  Ok(())
+fn bump_priority(db: State<'_, DbState>, task_id: String, delta: i64) -> Result<Priority, String> {
+  let conn = lock_db(&db)?;
+  let task = fetch_task_by_id(&conn, &task_id)?;
+  let priority = Priority::from_db(task.priority.to_db().saturating_add(delta).clamp(0, 2));
+  write_task_priority(&conn, &task, priority)?;
+  mark_snapshot_dirty(&db);
+  Ok(priority)
 }
 
+/// Moves a task to the trash. Its actions and tags stay attached so `restore_task`
+/// brings it back intact; `purge_deleted` removes it for good.
 #[tauri::command]
-fn create_task(
+fn delete_task(
   db: State<'_, DbState>,
   scheduler: State<'_, SchedulerState>,
-  input: NewTaskInput,
-) -> Result<TaskItem, String> {
-  validate_repeat_rule(&input.repeat_rule)?;
-  let (reminder_enabled, reminder_offset_minutes) = reminder_to_db(&input.reminder)?;
-
-  let title = input.title.trim();
-  if title.is_empty() {
-    return Err("Task title is required".to_string());
-  }
-
-  let task_id = format!("task_{}", Uuid::new_v4());
-  let repeat_type = input.repeat_rule.as_ref().map(|rule| rule.rule_type.clone());
-  let repeat_day_of_week = input
-    .repeat_rule
-    .as_ref()
-    .and_then(|rule| rule.day_of_week.clone())
-    .map(|days| serde_json::to_string(&days))
-    .transpose()
-    .map_err(|err| format!("Failed to encode repeat days of week: {err}"))?;
-  let repeat_day_of_month = input
-    .repeat_rule
-    .as_ref()
-    .and_then(|rule| rule.day_of_month.clone())
-    .map(|days| serde_json::to_string(&days))
-    .transpose()
-    .map_err(|err| format!("Failed to encode repeat days of month: {err}"))?;
-
-  let mut conn = open_connection(&db.db_path)?;
+  task_id: String,
+) -> Result<(), String> {
+  let mut conn = lock_db(&db)?;
   let tx = conn
     .transaction()
     .map_err(|err| format!("Failed to start transaction: {err}"))?;
-
-  tx
+  let task = fetch_task_by_id(&tx, &task_id)?;
+  let affected = tx
     .execute(
-      "INSERT INTO tasks (id, list_id, title, detail, completed, date, time, reminder, reminder_offset_minutes, repeat_type, repeat_day_of_week, repeat_day_of_month)
-       VALUES (?1, ?2, ?3, ?4, 0, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
-      params![
-        task_id,
-        input.list_id,
-        title,
-        input.detail.and_then(|v| {
-          let trimmed = v.trim().to_string();
-          if trimmed.is_empty() { None } else { Some(trimmed) }
-        }),
-        input.due_date,
-        input.time,
-        reminder_enabled,
-        reminder_offset_minutes,
-        repeat_type,
-        repeat_day_of_week,
-        repeat_day_of_month
-      ],
+      "UPDATE tasks SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+      params![task_id, now_epoch_ms()],
     )
-    .map_err(|err| format!("Failed to create task: {err}"))?;
+    .map_err(|err| format!("Failed to delete task: {err}"))?;
 
-  if let Some(actions) = &input.actions {
-    persist_task_actions(&tx, &task_id, actions)?;
+  if affected == 0 {
+    return Err("Task not found".to_string());
   }
+  record_task_change(&tx, &task_id, "deleted", None, None, None)?;
+  push_undo_entry(
+    &tx,
+    &DeletedEntity {
+      kind: "task".to_string(),
+      task: Some(task),
+      list: None,
+      task_ids: Vec::new(),
+      moved_to: None,
+    },
+  )?;
 
   tx
     .commit()
-    .map_err(|err| format!("Failed to commit task creation: {err}"))?;
+    .map_err(|err| format!("Failed to commit task deletion: {err}"))?;
+
   scheduler_wakeup(&scheduler);
+  mark_snapshot_dirty(&db);
+  Ok(())
+}
 
-  let conn = open_connection(&db.db_path)?;
-  fetch_task_by_id(&conn, &task_id)
+/// Tasks currently in the trash, most recently deleted first.
+#[tauri::command]
+fn list_deleted_tasks(db: State<'_, DbState>) -> Result<Vec<TaskItem>, String> {
+  let conn = lock_db(&db)?;
+  let mut stmt = conn
+    .prepare(&format!(
+      "SELECT {TASK_COLUMNS} FROM tasks t WHERE t.deleted_at IS NOT NULL ORDER BY t.deleted_at DESC, t.rowid DESC"
+    ))
+    .map_err(|err| format!("Failed to query deleted tasks: {err}"))?;
+  let mut tasks = stmt
+    .query_map([], |row| task_from_row(row, &HashMap::new()))
+    .map_err(|err| format!("Failed to map deleted tasks: {err}"))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|err| format!("Failed to parse deleted tasks: {err}"))?;
+  attach_task_relations(&conn, &mut tasks)?;
+  Ok(tasks)
 }
 
 #[tauri::command]
-fn save_task(
+fn restore_task(
   db: State<'_, DbState>,
   scheduler: State<'_, SchedulerState>,
-  task: SaveTaskInput,
+  task_id: String,
 ) -> Result<TaskItem, String> {
-  validate_repeat_rule(&task.repeat_rule)?;
-  let (reminder_enabled, reminder_offset_minutes) = reminder_to_db(&task.reminder)?;
+  let conn = lock_db(&db)?;
+  let affected = conn
+    .execute(
+      "UPDATE tasks SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+      params![task_id],
+    )
+    .map_err(|err| format!("Failed to restore task: {err}"))?;
 
-  let title = task.title.trim();
-  if title.is_empty() {
-    return Err("Task title is required".to_string());
+  if affected == 0 {
+    return Err("Deleted task not found".to_string());
   }
+  record_task_change(&conn, &task_id, "restored", None, None, None)?;
+  let task = fetch_task_by_id(&conn, &task_id)?;
 
-  let repeat_type = task.repeat_rule.as_ref().map(|rule| rule.rule_type.clone());
-  let repeat_day_of_week = task
-    .repeat_rule
-    .as_ref()
-    .and_then(|rule| rule.day_of_week.clone())
-    .map(|days| serde_json::to_string(&days))
-    .transpose()
-    .map_err(|err| format!("Failed to encode repeat days of week: {err}"))?;
-  let repeat_day_of_month = task
-    .repeat_rule
-    .as_ref()
-    .and_then(|rule| rule.day_of_month.clone())
-    .map(|days| serde_json::to_string(&days))
-    .transpose()
-    .map_err(|err| format!("Failed to encode repeat days of month: {err}"))?;
+  scheduler_wakeup(&scheduler);
+  mark_snapshot_dirty(&db);
+  Ok(task)
+}
 
-  let mut conn = open_connection(&db.db_path)?;
+/// Permanently removes trashed tasks deleted before `before_ms`, together with
+/// their actions, tags and reminder state. Passing the current time empties the trash.
+#[tauri::command]
+fn purge_deleted(db: State<'_, DbState>, before_ms: i64) -> Result<usize, String> {
+  let conn = lock_db(&db)?;
+  let purged = conn
+    .execute(
+      "DELETE FROM tasks WHERE deleted_at IS NOT NULL AND deleted_at < ?1",
+      params![before_ms],
+    )
+    .map_err(|err| format!("Failed to purge deleted tasks: {err}"))?;
+
+  mark_snapshot_dirty(&db);
+  Ok(purged)
+}
+
+#[tauri::command]
+fn clear_completed_tasks(db: State<'_, DbState>, scheduler: State<'_, SchedulerState>) -> Result<u64, String> {
+  let mut conn = lock_db(&db)?;
   let tx = conn
     .transaction()
     .map_err(|err| format!("Failed to start transaction: {err}"))?;
 
-  let affected = tx
+  let ids = {
+    let mut stmt = tx
+      .prepare("SELECT id FROM tasks WHERE completed = 1 AND deleted_at IS NULL")
+      .map_err(|err| format!("Failed to query completed tasks: {err}"))?;
+    let rows = stmt
+      .query_map([], |row| row.get::<_, String>(0))
+      .map_err(|err| format!("Failed to query completed tasks: {err}"))?;
+    rows
+      .collect::<Result<Vec<_>, _>>()
+      .map_err(|err| format!("Failed to read completed tasks: {err}"))?
+  };
+
+  // Recorded per task, like `delete_tasks`, so the change log sees each one leave.
+  let now = now_epoch_ms();
+  for id in &ids {
+    tx
+      .execute("UPDATE tasks SET deleted_at = ?2 WHERE id = ?1", params![id, now])
+      .map_err(|err| format!("Failed to clear completed tasks: {err}"))?;
+    record_task_change(&tx, id, "deleted", None, None, None)?;
+  }
+  tx
+    .commit()
+    .map_err(|err| format!("Failed to commit clearing completed tasks: {err}"))?;
+
+  scheduler_wakeup(&scheduler);
+  mark_snapshot_dirty(&db);
+  Ok(ids.len() as u64)
+}
+
+/// Records that the user engaged with a fired reminder. Firing only means the
+/// notification was shown; acknowledging is what clears it from the badge count.
+#[tauri::command]
+fn acknowledge_reminder(db: State<'_, DbState>, task_id: String, remind_at: i64) -> Result<(), String> {
+  let conn = lock_db(&db)?;
+  conn
     .execute(
-      "UPDATE tasks
-       SET list_id = ?2,
-           title = ?3,
-           detail = ?4,
-           completed = ?5,
-           date = ?6,
-           time = ?7,
-           reminder = ?8,
-           reminder_offset_minutes = ?9,
-           repeat_type = ?10,
-           repeat_day_of_week = ?11,
-           repeat_day_of_month = ?12,
-           updated_at = CURRENT_TIMESTAMP
-       WHERE id = ?1",
-      params![
-        task.id,
-        task.list_id,
-        title,
-        task.detail.and_then(|v| {
-          let trimmed = v.trim().to_string();
-          if trimmed.is_empty() { None } else { Some(trimmed) }
-        }),
-        if task.completed { 1 } else { 0 },
-        task.due_date,
-        task.time,
-        reminder_enabled,
-        reminder_offset_minutes,
-        repeat_type,
-        repeat_day_of_week,
-        repeat_day_of_month
-      ],
+      "UPDATE fired_reminders SET acknowledged_at = ?3
+       WHERE task_id = ?1 AND remind_at = ?2 AND acknowledged_at IS NULL",
+      params![task_id, remind_at, now_epoch_ms()],
     )
-    .map_err(|err| format!("Failed to update task: {err}"))?;
+    .map_err(|err| format!("Failed to acknowledge reminder: {err}"))?;
 
-  if affected == 0 {
-    return Err("Task not found".to_string());
+  // Acknowledging twice is a no-op; only a reminder that never fired is an error.
+  if !is_reminder_fired(&conn, &task_id, remind_at)? {
+    return Err("Reminder has not fired".to_string());
   }
+  Ok(())
+}
+
+/// Fired reminders the user hasn't acknowledged yet, for the unread badge.
+#[tauri::command]
+fn unacknowledged_reminder_count(db: State<'_, DbState>) -> Result<i64, String> {
+  let conn = lock_db(&db)?;
+  conn
+    .query_row(
+      "SELECT COUNT(*)
+       FROM fired_reminders f
+       JOIN tasks t ON t.id = f.task_id
+       WHERE f.acknowledged_at IS NULL AND t.deleted_at IS NULL",
+      [],
+      |row| row.get(0),
+    )
+    .map_err(|err| format!("Failed to count unacknowledged reminders: {err}"))
+}
+
+/// Collapses duplicate `(task_id, remind_at)` fired rows, keeping the earliest `fired_at`.
+/// The primary key already prevents duplicates; this is a safety net for raw database
+/// files restored from older schemas that lacked it.
+#[tauri::command]
+fn dedupe_fired_reminders(db: State<'_, DbState>) -> Result<usize, String> {
+  let conn = lock_db(&db)?;
+  conn
+    .execute(
+      "DELETE FROM fired_reminders
+       WHERE EXISTS (
+         SELECT 1 FROM fired_reminders f
+         WHERE f.task_id = fired_reminders.task_id
+           AND f.remind_at = fired_reminders.remind_at
+           AND (
+             f.fired_at < fired_reminders.fired_at
+             OR (f.fired_at = fired_reminders.fired_at AND f.rowid < fired_reminders.rowid)
+           )
+       )",
+      [],
+    )
+    .map_err(|err| format!("Failed to dedupe fired reminders: {err}"))
+}
 
-  persist_task_actions(&tx, &task.id, &task.actions.unwrap_or_default())?;
+#[tauri::command]
+fn clear_reminder_queue(db: State<'_, DbState>, scheduler: State<'_, SchedulerState>) -> Result<u64, String> {
+  let conn = lock_db(&db)?;
+  let deleted = conn
+    .execute("DELETE FROM fired_reminders", [])
+    .map_err(|err| format!("Failed to clear reminder queue: {err}"))?;
 
-  tx
-    .commit()
-    .map_err(|err| format!("Failed to commit task update: {err}"))?;
   scheduler_wakeup(&scheduler);
-
-  let conn = open_connection(&db.db_path)?;
-  fetch_task_by_id(&conn, &task.id)
+  Ok(deleted as u64)
 }
 
+/// Forgets that a task's reminders already fired so the scheduler notifies again.
 #[tauri::command]
-fn toggle_task_completed(
+fn reset_fired_reminders(
   db: State<'_, DbState>,
   scheduler: State<'_, SchedulerState>,
   task_id: String,
-) -> Result<TaskItem, String> {
-  let mut conn = open_connection(&db.db_path)?;
-  let task = fetch_task_by_id(&conn, &task_id)?;
-  let next = if task.completed { 0 } else { 1 };
+) -> Result<u64, String> {
+  let conn = lock_db(&db)?;
+  let deleted = delete_fired_reminders_for_task(&conn, &task_id)?;
+
+  scheduler_wakeup(&scheduler);
+  Ok(deleted as u64)
+}
+
+/// Sets `position` in `table` so `ordered_ids` come first, followed by the rest of
+/// `current` (the existing ids in display order) in their prior order.
+fn rewrite_positions(
+  conn: &Connection,
+  table: &str,
+  current: &[String],
+  ordered_ids: &[String],
+) -> Result<(), String> {
+  let mut order: Vec<&String> = Vec::new();
+  for id in ordered_ids {
+    if !current.contains(id) {
+      return Err(format!("Cannot reorder {table}: unknown id {id}"));
+    }
+    if !order.contains(&id) {
+      order.push(id);
+    }
+  }
+  order.extend(current.iter().filter(|id| !ordered_ids.contains(id)));
+
+  for (position, id) in order.iter().enumerate() {
+    conn
+      .execute(
+        &format!("UPDATE {table} SET position = ?2 WHERE id = ?1"),
+        params![id, position as i64],
+      )
+      .map_err(|err| format!("Failed to update {table} position: {err}"))?;
+  }
+  Ok(())
+}
 
+/// Rewrites list positions to follow `ordered_ids`; lists left out keep their relative
+/// order after the ones given.
+#[tauri::command]
+fn reorder_lists(db: State<'_, DbState>, ordered_ids: Vec<String>) -> Result<Vec<ListItem>, String> {
+  let mut conn = lock_db(&db)?;
   let tx = conn
     .transaction()
     .map_err(|err| format!("Failed to start transaction: {err}"))?;
 
+  let current: Vec<String> = load_lists(&tx)?.into_iter().map(|list| list.id).collect();
+  rewrite_positions(&tx, "lists", &current, &ordered_ids)?;
+
   tx
+    .commit()
+    .map_err(|err| format!("Failed to commit list reorder: {err}"))?;
+  mark_snapshot_dirty(&db);
+
+  load_lists(&conn)
+}
+
+fn list_task_ids(conn: &Connection, list_id: &str) -> Result<Vec<String>, String> {
+  let mut stmt = conn
+    .prepare("SELECT id FROM tasks WHERE list_id = ?1")
+    .map_err(|err| format!("Failed to query list tasks: {err}"))?;
+  let ids = stmt
+    .query_map(params![list_id], |row| row.get::<_, String>(0))
+    .map_err(|err| format!("Failed to map list tasks: {err}"))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|err| format!("Failed to read list tasks: {err}"));
+  ids
+}
+
+/// Records a deletion for `undo_last_delete`, dropping the oldest entries past
+/// `UNDO_LOG_LIMIT`.
+fn push_undo_entry(conn: &Connection, entry: &DeletedEntity) -> Result<(), String> {
+  let entity_id = match (&entry.task, &entry.list) {
+    (Some(task), _) => task.id.clone(),
+    (None, Some(list)) => list.id.clone(),
+    (None, None) => return Err("Undo entry has nothing to restore".to_string()),
+  };
+  let payload = serde_json::to_string(entry).map_err(|err| format!("Failed to encode undo entry: {err}"))?;
+  conn
     .execute(
-      "UPDATE tasks SET completed = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
-      params![task_id, next],
+      "INSERT INTO undo_log (kind, entity_id, payload, created_at) VALUES (?1, ?2, ?3, ?4)",
+      params![entry.kind, entity_id, payload, now_epoch_ms()],
     )
-    .map_err(|err| format!("Failed to toggle task completion: {err}"))?;
-
-  if !task.completed && next == 1 {
-    if let Some(next_date) = compute_next_repeat_date(&task) {
-      let next_task_id = format!("task_{}", Uuid::new_v4());
-      let (reminder_enabled, reminder_offset_minutes) = reminder_to_db(&task.reminder)?;
-      let repeat_type = task.repeat_rule.as_ref().map(|rule| rule.rule_type.clone());
-      let repeat_day_of_week = task
-        .repeat_rule
-        .as_ref()
-        .and_then(|rule| rule.day_of_week.clone())
-        .map(|days| serde_json::to_string(&days))
-        .transpose()
-        .map_err(|err| format!("Failed to encode repeat days of week: {err}"))?;
-      let repeat_day_of_month = task
-        .repeat_rule
-        .as_ref()
-        .and_then(|rule| rule.day_of_month.clone())
-        .map(|days| serde_json::to_string(&days))
-        .transpose()
-        .map_err(|err| format!("Failed to encode repeat days of month: {err}"))?;
+    .map_err(|err| format!("Failed to record undo entry: {err}"))?;
+  conn
+    .execute(
+      "DELETE FROM undo_log WHERE id NOT IN (SELECT id FROM undo_log ORDER BY id DESC LIMIT ?1)",
+      params![UNDO_LOG_LIMIT],
+    )
+    .map_err(|err| format!("Failed to trim undo log: {err}"))?;
+  Ok(())
+}
 
-      tx
-        .execute(
-          "INSERT INTO tasks (id, list_id, title, detail, completed, date, time, reminder, reminder_offset_minutes, repeat_type, repeat_day_of_week, repeat_day_of_month)
-           VALUES (?1, ?2, ?3, ?4, 0, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
-          params![
-            next_task_id,
-            task.list_id,
-            task.title,
-            task.detail,
-            next_date,
-            task.time,
-            reminder_enabled,
-            reminder_offset_minutes,
-            repeat_type,
-            repeat_day_of_week,
-            repeat_day_of_month
-          ],
-        )
-        .map_err(|err| format!("Failed to create next recurring task: {err}"))?;
+/// Brings a deleted task back: out of the trash if it is still there, otherwise
+/// re-inserted from the snapshot. Its list and any actions whose scheme has since
+/// been deleted are dropped.
+fn undo_task_delete(conn: &Connection, task: &TaskItem) -> Result<(), String> {
+  let deleted_at = conn
+    .query_row("SELECT deleted_at FROM tasks WHERE id = ?1", params![task.id], |row| {
+      row.get::<_, Option<i64>>(0)
+    })
+    .map(Some)
+    .or_else(|err| match err {
+      rusqlite::Error::QueryReturnedNoRows => Ok(None),
+      other => Err(other),
+    })
+    .map_err(|err| format!("Failed to look up deleted task: {err}"))?;
 
-      if let Some(actions) = task.actions.as_ref() {
-        persist_task_actions(&tx, &next_task_id, actions)?;
+  match deleted_at {
+    Some(Some(_)) => {
+      conn
+        .execute("UPDATE tasks SET deleted_at = NULL WHERE id = ?1", params![task.id])
+        .map_err(|err| format!("Failed to restore task: {err}"))?;
+    }
+    // Already restored from the trash.
+    Some(None) => return Ok(()),
+    None => {
+      let list_id = match task.list_id.as_deref() {
+        Some(list_id) if ensure_list_exists(conn, list_id).is_ok() => Some(list_id.to_string()),
+        _ => None,
+      };
+      let mut actions = Vec::new();
+      for action in task.actions.as_deref().unwrap_or_default() {
+        let exists: i64 = conn
+          .query_row(
+            "SELECT EXISTS(SELECT 1 FROM schemes WHERE id = ?1)",
+            params![action.scheme_id],
+            |row| row.get(0),
+          )
+          .map_err(|err| format!("Failed to look up scheme: {err}"))?;
+        if exists != 0 {
+          actions.push(action.clone());
+        }
       }
+      insert_task_row(
+        conn,
+        &TaskItem {
+          list_id,
+          actions: Some(actions),
+          ..task.clone()
+        },
+      )?;
     }
   }
-
-  tx
-    .commit()
-    .map_err(|err| format!("Failed to commit task toggle: {err}"))?;
-  scheduler_wakeup(&scheduler);
-
-  let conn = open_connection(&db.db_path)?;
-  fetch_task_by_id(&conn, &task_id)
+  record_task_change(conn, &task.id, "restored", None, None, None)
 }
 
-#[tauri::command]
-fn delete_task(
-  db: State<'_, DbState>,
-  scheduler: State<'_, SchedulerState>,
-  task_id: String,
+/// Recreates a deleted list at the end of the sidebar and moves back the tasks it
+/// held that are still where the deletion left them.
+fn undo_list_delete(
+  conn: &Connection,
+  list: &ListItem,
+  task_ids: &[String],
+  moved_to: Option<&str>,
 ) -> Result<(), String> {
-  let conn = open_connection(&db.db_path)?;
-  let affected = conn
-    .execute("DELETE FROM tasks WHERE id = ?1", params![task_id])
-    .map_err(|err| format!("Failed to delete task: {err}"))?;
+  conn
+    .execute(
+      "INSERT OR IGNORE INTO lists (id, name, icon, color, default_reminder_offset, position)
+       VALUES (?1, ?2, ?3, ?4, ?5, (SELECT COALESCE(MAX(position), -1) + 1 FROM lists))",
+      params![list.id, list.name, list.icon, list.color, list.default_reminder_offset],
+    )
+    .map_err(|err| format!("Failed to restore list: {err}"))?;
 
-  if affected == 0 {
-    return Err("Task not found".to_string());
+  let mut stmt = conn
+    .prepare("UPDATE tasks SET list_id = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2 AND list_id IS ?3")
+    .map_err(|err| format!("Failed to prepare list task restore: {err}"))?;
+  for task_id in task_ids {
+    stmt
+      .execute(params![list.id, task_id, moved_to])
+      .map_err(|err| format!("Failed to move task back to list: {err}"))?;
   }
-
-  scheduler_wakeup(&scheduler);
   Ok(())
 }
 
+/// Reverses the most recent task or list deletion made within `UNDO_WINDOW_MS`
+/// and returns what was restored, or `None` when there is nothing to undo.
 #[tauri::command]
-fn clear_completed_tasks(db: State<'_, DbState>, scheduler: State<'_, SchedulerState>) -> Result<u64, String> {
-  let conn = open_connection(&db.db_path)?;
-  let deleted = conn
-    .execute("DELETE FROM tasks WHERE completed = 1", [])
-    .map_err(|err| format!("Failed to clear completed tasks: {err}"))?;
+fn undo_last_delete(
+  db: State<'_, DbState>,
+  scheduler: State<'_, SchedulerState>,
+) -> Result<Option<DeletedEntity>, String> {
+  let mut conn = lock_db(&db)?;
+  let tx = conn
+    .transaction()
+    .map_err(|err| format!("Failed to start transaction: {err}"))?;
 
-  scheduler_wakeup(&scheduler);
-  Ok(deleted as u64)
-}
+  let latest = tx
+    .query_row(
+      "SELECT id, payload FROM undo_log WHERE created_at >= ?1 ORDER BY id DESC LIMIT 1",
+      params![now_epoch_ms() - UNDO_WINDOW_MS],
+      |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+    )
+    .map(Some)
+    .or_else(|err| match err {
+      rusqlite::Error::QueryReturnedNoRows => Ok(None),
+      other => Err(other),
+    })
+    .map_err(|err| format!("Failed to read undo log: {err}"))?;
+  let Some((entry_id, payload)) = latest else {
+    return Ok(None);
+  };
 
-#[tauri::command]
-fn clear_reminder_queue(db: State<'_, DbState>, scheduler: State<'_, SchedulerState>) -> Result<u64, String> {
-  let conn = open_connection(&db.db_path)?;
-  let deleted = conn
-    .execute("DELETE FROM fired_reminders", [])
-    .map_err(|err| format!("Failed to clear reminder queue: {err}"))?;
+  let mut entry: DeletedEntity =
+    serde_json::from_str(&payload).map_err(|err| format!("Failed to decode undo entry: {err}"))?;
+  match (&entry.task, &entry.list) {
+    (Some(task), _) => undo_task_delete(&tx, task)?,
+    (None, Some(list)) => undo_list_delete(&tx, list, &entry.task_ids, entry.moved_to.as_deref())?,
+    (None, None) => return Err("Undo entry has nothing to restore".to_string()),
+  }
+  tx
+    .execute("DELETE FROM undo_log WHERE id = ?1", params![entry_id])
+    .map_err(|err| format!("Failed to clear undo entry: {err}"))?;
+
+  tx
+    .commit()
+    .map_err(|err| format!("Failed to commit undo: {err}"))?;
+  if let Some(task) = entry.task.as_ref() {
+    entry.task = Some(fetch_task_by_id(&conn, &task.id)?);
+  }
 
   scheduler_wakeup(&scheduler);
-  Ok(deleted as u64)
+  mark_snapshot_dirty(&db);
+  Ok(Some(entry))
 }
 
+/// Deletes a list. With `reassign_to`, its tasks move to that list first;
+/// otherwise they become unfiled. Returns the number of tasks reassigned.
 #[tauri::command]
-fn delete_list(db: State<'_, DbState>, list_id: String) -> Result<(), String> {
+fn delete_list(db: State<'_, DbState>, list_id: String, reassign_to: Option<String>) -> Result<usize, String> {
   if list_id == "list_today" {
     return Err("Default list cannot be deleted".to_string());
   }
 
-  let conn = open_connection(&db.db_path)?;
-  let affected = conn
+  let mut conn = lock_db(&db)?;
+  let tx = conn
+    .transaction()
+    .map_err(|err| format!("Failed to start transaction: {err}"))?;
+  let list = fetch_list_by_id(&tx, &list_id)?;
+  let task_ids = list_task_ids(&tx, &list_id)?;
+
+  let mut reassigned = 0;
+  if let Some(target_list_id) = reassign_to.as_deref() {
+    if target_list_id == list_id {
+      return Err("Cannot reassign tasks to the list being deleted".to_string());
+    }
+    ensure_list_exists(&tx, target_list_id)?;
+    reassigned = tx
+      .execute(
+        "UPDATE tasks SET list_id = ?2, updated_at = CURRENT_TIMESTAMP WHERE list_id = ?1",
+        params![list_id, target_list_id],
+      )
+      .map_err(|err| format!("Failed to reassign list tasks: {err}"))?;
+  }
+
+  let affected = tx
     .execute("DELETE FROM lists WHERE id = ?1", params![list_id])
     .map_err(|err| format!("Failed to delete list: {err}"))?;
 
   if affected == 0 {
     return Err("List not found".to_string());
   }
+  push_undo_entry(
+    &tx,
+    &DeletedEntity {
+      kind: "list".to_string(),
+      task: None,
+      list: Some(list),
+      task_ids,
+      moved_to: reassign_to,
+    },
+  )?;
 
-  Ok(())
+  tx
+    .commit()
+    .map_err(|err| format!("Failed to commit list deletion: {err}"))?;
+  mark_snapshot_dirty(&db);
+  Ok(reassigned)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -1582,18 +6732,27 @@ pub fn run() {
         .map_err(|err| format!("Failed to create app data dir: {err}"))?;
 
       let db_path = app_data_dir.join("linkflow.db");
+      let cache_path = app_data_dir.join("snapshot-cache.json");
       init_database(&db_path)?;
 
       let wakeup = Arc::new(Notify::new());
+      let cache_dirty = Arc::new(Notify::new());
       app.manage(DbState {
         db_path: db_path.clone(),
+        conn: Arc::new(Mutex::new(open_connection(&db_path)?)),
+        cache_path: cache_path.clone(),
+        cache_dirty: cache_dirty.clone(),
       });
       app.manage(SchedulerState {
         wakeup: wakeup.clone(),
       });
 
       let app_handle = app.handle().clone();
-      tauri::async_runtime::spawn(scheduler_loop(app_handle, db_path, wakeup));
+      tauri::async_runtime::spawn(scheduler_loop(app_handle, db_path.clone(), wakeup));
+
+      // Refresh the cache once at startup so it reflects any migrations applied above.
+      cache_dirty.notify_one();
+      tauri::async_runtime::spawn(snapshot_cache_loop(db_path, cache_path, cache_dirty));
       Ok(())
     })
     .plugin(tauri_plugin_shell::init())
@@ -1601,22 +6760,674 @@ pub fn run() {
     .plugin(tauri_plugin_notification::init())
     .invoke_handler(tauri::generate_handler![
       get_app_snapshot,
+      get_cached_snapshot,
       export_backup,
+      export_markdown,
+      export_db_file,
       import_backup,
+      restore_db_file,
+      rebuild,
+      check_database_integrity,
+      compact_database,
+      action_param_mismatches,
       debug_next_reminder,
+      debug_task_reminders,
+      list_upcoming_reminders,
+      reminders_until,
+      grace_window_reminders,
+      effective_reminder,
+      reminders_by_list,
+      simulate_reminders,
+      compute_reminder_preview,
+      unschedulable_reminders,
+      focus_task,
+      list_tasks_by_tag,
+      search_tasks,
+      list_tasks_paged,
+      list_tasks_sorted,
+      list_tasks_due,
+      unbounded_recurring_tasks,
+      tasks_filtered,
+      get_tasks_by_ids,
+      list_templates,
+      task_to_template,
+      storage_by_list,
+      completion_rate,
+      task_history,
+      get_task_note,
+      set_task_note,
+      move_long_details_to_notes,
+      list_snoozes,
+      snooze_reminder,
+      snooze_until,
+      handle_reminder_action,
+      cancel_snooze,
+      get_setting,
+      set_setting,
+      scheduler_config,
+      notification_permission,
+      notification_permission_status,
+      request_notification_permission,
+      test_notification,
       create_list,
+      get_list,
       update_list,
+      update_lists_style,
+      reorder_lists,
       create_scheme,
+      get_scheme,
       update_scheme,
+      set_scheme_enabled,
+      reorder_schemes,
       delete_scheme,
+      restore_default_schemes,
+      supported_schemes,
+      resolve_action_url,
+      run_shell_action,
+      validate_scheme_params,
+      resolve_actions_for_tasks,
       create_task,
       save_task,
+      move_task,
+      move_task_position,
+      file_unfiled_tasks,
+      set_task_time,
       toggle_task_completed,
+      bulk_toggle_completed,
+      complete_tasks,
+      delete_tasks,
+      import_tasks_resolved,
+      normalize_all_times,
+      set_tasks_priority,
+      bump_priority,
       delete_task,
+      list_deleted_tasks,
+      restore_task,
+      undo_last_delete,
+      purge_deleted,
       clear_completed_tasks,
       clear_reminder_queue,
+      reset_fired_reminders,
+      dedupe_fired_reminders,
+      acknowledge_reminder,
+      unacknowledged_reminder_count,
       delete_list
     ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn rule(rule_type: &str) -> RepeatRule {
+    RepeatRule {
+      rule_type: rule_type.to_string(),
+      ..RepeatRule::default()
+    }
+  }
+
+  fn task_on(date: &str, repeat_rule: RepeatRule) -> TaskItem {
+    TaskItem {
+      id: "task_test".to_string(),
+      title: "Test".to_string(),
+      due_date: Some(date.to_string()),
+      repeat_rule: Some(repeat_rule),
+      ..TaskItem::default()
+    }
+  }
+
+  fn weekdays_only(interval: Option<u32>) -> RepeatRule {
+    RepeatRule {
+      interval,
+      skip_weekends: true,
+      ..rule("daily")
+    }
+  }
+
+  #[test]
+  fn skip_weekends_moves_friday_to_monday() {
+    let friday = task_on("2026-10-16", weekdays_only(None));
+    assert_eq!(compute_next_repeat_date(&friday).as_deref(), Some("2026-10-19"));
+    // The recurrence spawned on completion uses the same date.
+    assert_eq!(next_recurrence_date(&friday, false).as_deref(), Some("2026-10-19"));
+  }
+
+  #[test]
+  fn skip_weekends_rolls_saturday_and_sunday_forward() {
+    let saturday = task_on("2026-10-17", weekdays_only(None));
+    assert_eq!(compute_next_repeat_date(&saturday).as_deref(), Some("2026-10-19"));
+    let thursday = task_on("2026-10-15", weekdays_only(None));
+    assert_eq!(compute_next_repeat_date(&thursday).as_deref(), Some("2026-10-16"));
+  }
+
+  #[test]
+  fn skip_weekends_applies_after_the_interval() {
+    // Thursday + 2 days lands on Saturday.
+    let thursday = task_on("2026-10-15", weekdays_only(Some(2)));
+    assert_eq!(compute_next_repeat_date(&thursday).as_deref(), Some("2026-10-19"));
+    // Friday + 2 days lands on Sunday.
+    let friday = task_on("2026-10-16", weekdays_only(Some(2)));
+    assert_eq!(compute_next_repeat_date(&friday).as_deref(), Some("2026-10-19"));
+    // Friday + 4 days is already a weekday.
+    let friday = task_on("2026-10-16", weekdays_only(Some(4)));
+    assert_eq!(compute_next_repeat_date(&friday).as_deref(), Some("2026-10-20"));
+  }
+
+  #[test]
+  fn daily_without_skip_weekends_lands_on_saturday() {
+    let friday = task_on("2026-10-16", rule("daily"));
+    assert_eq!(compute_next_repeat_date(&friday).as_deref(), Some("2026-10-17"));
+  }
+
+  #[test]
+  fn skip_weekends_is_rejected_on_non_daily_rules() {
+    let weekly = RepeatRule {
+      day_of_week: Some(vec![1]),
+      skip_weekends: true,
+      ..rule("weekly")
+    };
+    assert!(validate_repeat_rule(&Some(weekly)).is_err());
+    let monthly = RepeatRule {
+      day_of_month: Some(vec![1]),
+      skip_weekends: true,
+      ..rule("monthly")
+    };
+    assert!(validate_repeat_rule(&Some(monthly)).is_err());
+    assert!(validate_repeat_rule(&Some(weekdays_only(None))).is_ok());
+  }
+
+  /// A migrated, seeded database file that is removed (with its WAL files) on drop.
+  struct TempDb {
+    path: PathBuf,
+  }
+
+  impl TempDb {
+    fn new() -> Self {
+      let path = std::env::temp_dir().join(format!("linkflow-test-{}.db", Uuid::new_v4()));
+      init_database(&path).expect("init test database");
+      TempDb { path }
+    }
+
+    fn open(&self) -> Connection {
+      open_connection(&self.path).expect("open test database")
+    }
+  }
+
+  impl Drop for TempDb {
+    fn drop(&mut self) {
+      for suffix in ["", "-wal", "-shm"] {
+        let _ = fs::remove_file(format!("{}{suffix}", self.path.display()));
+      }
+    }
+  }
+
+  /// Completes the task the way `toggle_task_completed` does, returning the spawned id.
+  fn complete_and_spawn(conn: &Connection, task_id: &str) -> Option<String> {
+    let task = fetch_task_by_id(conn, task_id).expect("fetch task");
+    set_task_completion(conn, task_id, true).expect("complete task");
+    next_recurrence_date(&task, false).map(|next| spawn_recurrence(conn, &task, next).expect("spawn recurrence"))
+  }
+
+  #[test]
+  fn daily_repeat_with_count_three_yields_three_instances() {
+    let db = TempDb::new();
+    let conn = db.open();
+    let first = task_on(
+      "2026-10-16",
+      RepeatRule {
+        count: Some(3),
+        ..rule("daily")
+      },
+    );
+    insert_task_row(&conn, &first).expect("insert task");
+
+    let mut current = first.id.clone();
+    let mut dates = vec![first.due_date.clone().unwrap_or_default()];
+    while let Some(next_id) = complete_and_spawn(&conn, &current) {
+      dates.push(fetch_task_by_id(&conn, &next_id).expect("fetch spawned").due_date.unwrap_or_default());
+      current = next_id;
+      assert!(dates.len() <= 3, "series kept spawning: {dates:?}");
+    }
+
+    assert_eq!(dates, ["2026-10-16", "2026-10-17", "2026-10-18"]);
+    let total: i64 = conn
+      .query_row("SELECT COUNT(*) FROM tasks WHERE title = 'Test'", [], |row| row.get(0))
+      .expect("count tasks");
+    assert_eq!(total, 3);
+  }
+
+  #[test]
+  fn repeat_until_stops_after_the_end_date() {
+    let task = task_on(
+      "2026-10-16",
+      RepeatRule {
+        until: Some("2026-10-17".to_string()),
+        ..rule("daily")
+      },
+    );
+    assert_eq!(compute_next_repeat_date(&task).as_deref(), Some("2026-10-17"));
+    let last = TaskItem {
+      due_date: Some("2026-10-17".to_string()),
+      ..task
+    };
+    assert_eq!(compute_next_repeat_date(&last), None);
+  }
+
+  #[test]
+  fn normalize_repeat_rule_sorts_and_dedupes_days() {
+    let weekly = RepeatRule {
+      day_of_week: Some(vec![5, 1, 3, 1, 5]),
+      ..rule("weekly")
+    };
+    let normalized = normalize_repeat_rule(&Some(weekly)).expect("valid weekly rule").expect("rule kept");
+    assert_eq!(normalized.day_of_week, Some(vec![1, 3, 5]));
+
+    let monthly = RepeatRule {
+      day_of_month: Some(vec![31, 1, 15, 15, 1]),
+      ..rule("monthly")
+    };
+    let normalized = normalize_repeat_rule(&Some(monthly)).expect("valid monthly rule").expect("rule kept");
+    assert_eq!(normalized.day_of_month, Some(vec![1, 15, 31]));
+  }
+
+  #[test]
+  fn normalize_repeat_rule_still_rejects_out_of_range_days() {
+    let weekly = RepeatRule {
+      day_of_week: Some(vec![7, 1, 1]),
+      ..rule("weekly")
+    };
+    assert!(normalize_repeat_rule(&Some(weekly)).is_err());
+  }
+
+  fn utc_ms(value: &str) -> i64 {
+    DateTime::parse_from_rfc3339(value).expect("valid RFC 3339").timestamp_millis()
+  }
+
+  fn timed_task_in(timezone: Option<&str>, due_date: &str, time: &str) -> TaskItem {
+    TaskItem {
+      id: "task_test".to_string(),
+      title: "Test".to_string(),
+      due_date: Some(due_date.to_string()),
+      time: Some(time.to_string()),
+      timezone: timezone.map(str::to_string),
+      reminder: Some(Reminder {
+        reminder_type: "relative".to_string(),
+        ..Reminder::default()
+      }),
+      ..TaskItem::default()
+    }
+  }
+
+  #[test]
+  fn remind_at_uses_the_task_zone_not_the_system_zone() {
+    // The same wall-clock time in two zones gives two fixed instants, whatever
+    // zone the machine running the scheduler is in.
+    let shanghai = timed_task_in(Some("Asia/Shanghai"), "2026-10-16", "09:00");
+    assert_eq!(
+      compute_remind_at(&shanghai, None, DstAmbiguity::Earliest),
+      Some(utc_ms("2026-10-16T01:00:00Z"))
+    );
+    let new_york = timed_task_in(Some("America/New_York"), "2026-10-16", "09:00");
+    assert_eq!(
+      compute_remind_at(&new_york, None, DstAmbiguity::Earliest),
+      Some(utc_ms("2026-10-16T13:00:00Z"))
+    );
+  }
+
+  #[test]
+  fn remind_at_without_a_zone_follows_the_system_zone() {
+    let floating = timed_task_in(None, "2026-10-16", "09:00");
+    let expected = Local
+      .from_local_datetime(&date("2026-10-16").and_hms_opt(9, 0, 0).expect("valid time"))
+      .earliest()
+      .map(|dt| dt.timestamp_millis());
+    assert_eq!(compute_remind_at(&floating, None, DstAmbiguity::Earliest), expected);
+  }
+
+  #[test]
+  fn spring_forward_gap_rolls_to_the_first_valid_minute() {
+    // New York skips 02:00-03:00 on 2026-03-08; 02:30 becomes 03:00 EDT.
+    let task = timed_task_in(Some("America/New_York"), "2026-03-08", "02:30");
+    assert_eq!(
+      compute_remind_at(&task, None, DstAmbiguity::Earliest),
+      Some(utc_ms("2026-03-08T07:00:00Z"))
+    );
+  }
+
+  #[test]
+  fn fall_back_ambiguity_follows_the_setting() {
+    // New York repeats 01:00-02:00 on 2026-11-01: first in EDT, then in EST.
+    let task = timed_task_in(Some("America/New_York"), "2026-11-01", "01:30");
+    assert_eq!(
+      compute_remind_at(&task, None, DstAmbiguity::Earliest),
+      Some(utc_ms("2026-11-01T05:30:00Z"))
+    );
+    assert_eq!(
+      compute_remind_at(&task, None, DstAmbiguity::Latest),
+      Some(utc_ms("2026-11-01T06:30:00Z"))
+    );
+  }
+
+  #[test]
+  fn open_connection_enables_wal() {
+    let db = TempDb::new();
+    let mode: String = db
+      .open()
+      .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+      .expect("read journal mode");
+    assert_eq!(mode, "wal");
+  }
+
+  #[test]
+  fn two_connections_can_write_concurrently() {
+    let db = TempDb::new();
+    let writers: Vec<_> = (0..2)
+      .map(|writer| {
+        let path = db.path.clone();
+        std::thread::spawn(move || {
+          let conn = open_connection(&path).expect("open writer connection");
+          for index in 0..50 {
+            let task = TaskItem {
+              id: format!("task_writer_{writer}_{index}"),
+              title: format!("Writer {writer} #{index}"),
+              ..TaskItem::default()
+            };
+            insert_task_row(&conn, &task).expect("concurrent insert");
+          }
+        })
+      })
+      .collect();
+    for writer in writers {
+      writer.join().expect("writer thread");
+    }
+
+    let count: i64 = db
+      .open()
+      .query_row("SELECT COUNT(*) FROM tasks WHERE id LIKE 'task_writer_%'", [], |row| row.get(0))
+      .expect("count tasks");
+    assert_eq!(count, 100);
+  }
+
+  #[test]
+  fn relative_reminder_requires_date_and_time() {
+    let dated_only = TaskItem {
+      time: None,
+      ..timed_task_in(None, "2026-10-16", "09:00")
+    };
+    assert_eq!(
+      validate_task_schedule(&dated_only),
+      Err("A reminder requires both a date and a time".to_string())
+    );
+    let timed_only = TaskItem {
+      due_date: None,
+      ..timed_task_in(None, "2026-10-16", "09:00")
+    };
+    assert_eq!(
+      validate_task_schedule(&timed_only),
+      Err("A reminder requires both a date and a time".to_string())
+    );
+    assert_eq!(validate_task_schedule(&timed_task_in(None, "2026-10-16", "09:00")), Ok(()));
+  }
+
+  fn save_input(task: &TaskItem) -> SaveTaskInput {
+    SaveTaskInput {
+      id: task.id.clone(),
+      list_id: task.list_id.clone(),
+      title: task.title.clone(),
+      detail: task.detail.clone(),
+      completed: task.completed,
+      due_date: task.due_date.clone(),
+      time: task.time.clone(),
+      reminder: task.reminder.clone(),
+      repeat_rule: task.repeat_rule.clone(),
+      actions: task.actions.clone(),
+      priority: None,
+      tags: None,
+      timezone: None,
+      color: None,
+    }
+  }
+
+  fn pending_remind_ats(conn: &Connection, now_ms: i64, task_id: &str) -> Vec<i64> {
+    collect_reminder_candidates(conn, now_ms, false)
+      .expect("collect candidates")
+      .into_iter()
+      .filter(|candidate| candidate.task_id == task_id)
+      .map(|candidate| candidate.remind_at_ms)
+      .collect()
+  }
+
+  #[test]
+  fn rescheduling_a_task_lets_the_new_reminder_fire() {
+    let db = TempDb::new();
+    let conn = db.open();
+    let task = timed_task_in(Some("UTC"), "2026-10-16", "09:00");
+    insert_task_row(&conn, &task).expect("insert task");
+    let nine = utc_ms("2026-10-16T09:00:00Z");
+    let ten = utc_ms("2026-10-16T10:00:00Z");
+    assert!(mark_reminder_fired(&conn, &task.id, nine, nine).expect("mark fired"));
+
+    let mut edited = save_input(&task);
+    edited.time = Some("10:00".to_string());
+    apply_task_save(&conn, edited).expect("save task");
+    assert_eq!(pending_remind_ats(&conn, ten, &task.id), vec![ten]);
+
+    // Moving it back to 09:00 must not be suppressed by the earlier firing either.
+    apply_task_save(&conn, save_input(&task)).expect("save task");
+    assert_eq!(pending_remind_ats(&conn, nine, &task.id), vec![nine]);
+  }
+
+  #[test]
+  fn saving_without_schedule_changes_keeps_fired_reminders() {
+    let db = TempDb::new();
+    let conn = db.open();
+    let task = timed_task_in(Some("UTC"), "2026-10-16", "09:00");
+    insert_task_row(&conn, &task).expect("insert task");
+    let nine = utc_ms("2026-10-16T09:00:00Z");
+    mark_reminder_fired(&conn, &task.id, nine, nine).expect("mark fired");
+
+    let mut renamed = save_input(&task);
+    renamed.title = "Renamed".to_string();
+    apply_task_save(&conn, renamed).expect("save task");
+    assert!(is_reminder_fired(&conn, &task.id, nine).expect("check fired"));
+  }
+
+  #[test]
+  fn load_app_snapshot_orders_live_tasks_and_hides_trashed_ones() {
+    let db = TempDb::new();
+    let conn = db.open();
+    for id in ["task_first", "task_second", "task_trashed"] {
+      let task = TaskItem {
+        id: id.to_string(),
+        ..task_on("2026-10-16", rule("none"))
+      };
+      insert_task_row(&conn, &task).expect("insert task");
+    }
+    conn
+      .execute("UPDATE tasks SET deleted_at = 1 WHERE id = 'task_trashed'", [])
+      .expect("trash task");
+
+    // Same date and no time, so the rowid tie-breaker puts the newer task first.
+    let snapshot = load_app_snapshot(&conn).expect("load snapshot");
+    let ids: Vec<&str> = snapshot.tasks.iter().map(|task| task.id.as_str()).collect();
+    assert_eq!(ids, vec!["task_second", "task_first"]);
+  }
+
+  fn db_state(db: &TempDb) -> DbState {
+    DbState {
+      db_path: db.path.clone(),
+      conn: Arc::new(Mutex::new(db.open())),
+      cache_path: db.path.with_extension("cache.json"),
+      cache_dirty: Arc::new(Notify::new()),
+    }
+  }
+
+  #[test]
+  fn lock_db_reuses_one_connection() {
+    let db = TempDb::new();
+    let state = db_state(&db);
+    // Temp tables live only as long as the connection that made them, so seeing
+    // one again means no new connection was opened in between.
+    lock_db(&state)
+      .expect("lock")
+      .execute_batch("CREATE TEMP TABLE probe (id INTEGER);")
+      .expect("create temp table");
+    for _ in 0..3 {
+      let exists: i64 = lock_db(&state)
+        .expect("lock")
+        .query_row("SELECT COUNT(*) FROM temp.sqlite_master WHERE name = 'probe'", [], |row| row.get(0))
+        .expect("look up temp table");
+      assert_eq!(exists, 1);
+    }
+  }
+
+  #[test]
+  fn lock_db_recovers_after_a_panic() {
+    let db = TempDb::new();
+    let state = db_state(&db);
+    let conn = state.conn.clone();
+    let panicked = std::thread::spawn(move || {
+      let _guard = conn.lock().expect("lock");
+      panic!("command failed while holding the connection");
+    })
+    .join();
+    assert!(panicked.is_err());
+    assert!(state.conn.is_poisoned());
+
+    let count: i64 = lock_db(&state)
+      .expect("lock after panic")
+      .query_row("SELECT COUNT(*) FROM lists", [], |row| row.get(0))
+      .expect("query after panic");
+    assert!(count > 0);
+  }
+
+  #[test]
+  fn copy_database_file_keeps_commits_still_in_the_wal() {
+    let source = TempDb::new();
+    let writer = source.open();
+    writer
+      .pragma_update(None, "wal_autocheckpoint", 0)
+      .expect("disable autocheckpoint");
+    let task = TaskItem {
+      id: "task_in_wal".to_string(),
+      title: "Only in the WAL".to_string(),
+      ..TaskItem::default()
+    };
+    insert_task_row(&writer, &task).expect("insert task");
+
+    let dest = std::env::temp_dir().join(format!("linkflow-test-copy-{}.db", Uuid::new_v4()));
+    copy_database_file(&source.path, &dest).expect("copy database");
+    let copied = Connection::open(&dest).expect("open copy");
+    let found = fetch_task_by_id(&copied, "task_in_wal").map(|task| task.title);
+    drop(copied);
+    let _ = fs::remove_file(&dest);
+    assert_eq!(found, Ok("Only in the WAL".to_string()));
+  }
+
+  #[test]
+  fn swap_in_database_file_replaces_the_live_file() {
+    let live = TempDb::new();
+    let mut conn = live.open();
+    insert_task_row(
+      &conn,
+      &TaskItem {
+        id: "task_old".to_string(),
+        title: "Old".to_string(),
+        ..TaskItem::default()
+      },
+    )
+    .expect("insert old task");
+
+    let source = TempDb::new();
+    insert_task_row(
+      &source.open(),
+      &TaskItem {
+        id: "task_new".to_string(),
+        title: "New".to_string(),
+        ..TaskItem::default()
+      },
+    )
+    .expect("insert new task");
+    let staging = live.path.with_extension("db.restore");
+    copy_database_file(&source.path, &staging).expect("stage copy");
+
+    swap_in_database_file(&mut conn, &live.path, &staging).expect("swap database");
+    assert!(fetch_task_by_id(&conn, "task_new").is_ok());
+    assert!(fetch_task_by_id(&conn, "task_old").is_err());
+    drop(conn);
+
+    let dir = live.path.parent().expect("temp dir");
+    let stem = live.path.file_name().and_then(|name| name.to_str()).expect("file name");
+    for entry in fs::read_dir(dir).expect("read temp dir").flatten() {
+      let name = entry.file_name().to_string_lossy().into_owned();
+      if name.starts_with(&format!("{stem}.bak-")) {
+        let _ = fs::remove_file(entry.path());
+      }
+    }
+  }
+
+  fn date(value: &str) -> NaiveDate {
+    parse_date_ymd(value).expect("valid test date")
+  }
+
+  #[test]
+  fn next_weekly_date_same_weekday_only_moves_a_full_week() {
+    // 2026-10-16 is a Friday (5).
+    assert_eq!(next_weekly_date(date("2026-10-16"), &[5]), Some(date("2026-10-23")));
+  }
+
+  #[test]
+  fn next_weekly_date_picks_the_next_listed_day() {
+    // Wednesday -> Friday, skipping Thursday which is not listed.
+    assert_eq!(next_weekly_date(date("2026-10-14"), &[1, 3, 5]), Some(date("2026-10-16")));
+    // Monday -> Wednesday.
+    assert_eq!(next_weekly_date(date("2026-10-12"), &[1, 3, 5]), Some(date("2026-10-14")));
+  }
+
+  #[test]
+  fn next_weekly_date_wraps_into_the_next_week() {
+    // Friday -> next Monday.
+    assert_eq!(next_weekly_date(date("2026-10-16"), &[1, 3, 5]), Some(date("2026-10-19")));
+    // Saturday -> Sunday, which starts the next week.
+    assert_eq!(next_weekly_date(date("2026-10-17"), &[0]), Some(date("2026-10-18")));
+  }
+
+  #[test]
+  fn next_weekly_date_without_days_is_none() {
+    assert_eq!(next_weekly_date(date("2026-10-16"), &[]), None);
+  }
+
+  #[test]
+  fn posix_quote_keeps_metacharacters_literal() {
+    assert_eq!(posix_quote("plain"), "'plain'");
+    assert_eq!(posix_quote("it's"), "'it'\\''s'");
+    assert_eq!(posix_quote("$(rm -rf ~); `x` && y"), "'$(rm -rf ~); `x` && y'");
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn posix_quote_round_trips_through_sh() {
+    for value in ["it's", "a b", "$HOME", "x'; echo pwned; '", "\"double\"", "back\\slash"] {
+      let output = std::process::Command::new("sh")
+        .args(["-c", &format!("printf %s {}", posix_quote(value))])
+        .output()
+        .expect("run sh");
+      assert_eq!(String::from_utf8_lossy(&output.stdout), value);
+    }
+  }
+
+  #[test]
+  fn cmd_quote_wraps_safe_values() {
+    assert_eq!(cmd_quote("C:\\Users\\me\\notes.txt").as_deref(), Some("\"C:\\Users\\me\\notes.txt\""));
+    assert_eq!(cmd_quote("hello world").as_deref(), Some("\"hello world\""));
+  }
+
+  #[test]
+  fn cmd_quote_refuses_metacharacters() {
+    for value in ["a\" & calc", "%PATH%", "a^b", "a & b", "a | b", "a < b", "a > b", "!VAR!", "a\r\nb"] {
+      assert_eq!(cmd_quote(value), None, "{value:?} should be refused");
+    }
+  }
+}
@@ -1,5 +1,6 @@
 import { isTauri } from '@tauri-apps/api/core';
-import { isPermissionGranted } from '@tauri-apps/plugin-notification';
+import { listen } from '@tauri-apps/api/event';
+import { isPermissionGranted, onAction, registerActionTypes } from '@tauri-apps/plugin-notification';
 import { open as openExternal } from '@tauri-apps/plugin-shell';
 import { Archive, CalendarDays, CheckCircle2, ListTodo } from 'lucide-react';
 import { useEffect, useRef, useState } from 'react';
@@ -12,7 +13,8 @@ import { SettingsModal } from './components/modals/SettingsModal';
 import { Sidebar } from './components/sidebar/Sidebar';
 import { useAppStore, type ActiveView } from './store/useAppStore';
 import { executeTaskAction } from './utils/actionEngine';
-import type { List, RepeatRule, Task } from './types/models';
+import { schemeBoundTaskCount } from './utils/backendApi';
+import type { List, ReminderFiredEvent, RepeatRule, Task } from './types/models';
 
 const ALL_TASKS_LIST_ID = 'list_today';
 const isMacDesktop = () => /Macintosh|Mac OS X/i.test(window.navigator.userAgent);
@@ -67,7 +69,11 @@ function isRepeatRuleMatchingDate(repeat: RepeatRule | null | undefined, date: s
   }
 
   if (repeat.type === 'daily') {
-    return true;
+    if (!repeat.skipWeekends) {
+      return true;
+    }
+    const day = getWeekdayIndexMondayFirst(date);
+    return day !== null && day < 5;
   }
 
   if (repeat.type === 'weekly') {
@@ -127,7 +133,9 @@ function App() {
     addTaskFromDraft,
     resetDraftTask,
     toggleTaskCompleted,
+    handleReminderAction,
     deleteTask,
+    undoLastDelete,
     clearCompletedTasks,
     clearReminderQueue,
     updateTask,
@@ -150,6 +158,8 @@ function App() {
   const [searchQuery, setSearchQuery] = useState('');
   const [taskFilter, setTaskFilter] = useState<'all' | 'today' | 'overdue' | 'upcoming'>('all');
   const [notificationPermissionGranted, setNotificationPermissionGranted] = useState<boolean | null>(null);
+  // Fired reminders awaiting Complete/Snooze in the app; desktop notifications have no buttons.
+  const [pendingReminders, setPendingReminders] = useState<ReminderFiredEvent[]>([]);
   const quickEntryRef = useRef<TaskInputAreaHandle | null>(null);
 
   useEffect(() => {
@@ -220,6 +230,136 @@ function App() {
     };
   }, []);
 
+  useEffect(() => {
+    if (!isTauri()) {
+      return;
+    }
+
+    let disposed = false;
+    let unlisten: (() => void) | null = null;
+
+    const listenForReminderActions = async () => {
+      try {
+        // Action buttons are only supported by the mobile notification backends.
+        await registerActionTypes([
+          {
+            id: 'task-reminder',
+            actions: [
+              { id: 'complete', title: '完成' },
+              { id: 'snooze', title: '稍后提醒（10 分钟）' },
+            ],
+          },
+        ]);
+        const listener = await onAction((event) => {
+          const { actionId, notification } = event as unknown as {
+            actionId: string;
+            notification: {
+              extra?: {
+                taskId?: string;
+                remindAt?: number;
+                reminders?: { taskId: string; remindAt: number }[];
+              };
+            };
+          };
+          if (actionId !== 'complete' && actionId !== 'snooze') {
+            return;
+          }
+          // Grouped notifications list every task they cover; single ones carry one.
+          const extra = notification.extra;
+          const targets =
+            extra?.reminders ??
+            (extra?.taskId && extra.remindAt !== undefined
+              ? [{ taskId: extra.taskId, remindAt: extra.remindAt }]
+              : []);
+          for (const { taskId, remindAt } of targets) {
+            void handleReminderAction(taskId, remindAt, actionId).catch((error) => {
+              console.error('Failed to handle reminder action', error);
+            });
+          }
+        });
+        if (disposed) {
+          void listener.unregister();
+        } else {
+          unlisten = () => void listener.unregister();
+        }
+      } catch {
+        // Desktop notifications have no action buttons; the in-app reminder cards cover them.
+      }
+    };
+
+    void listenForReminderActions();
+
+    return () => {
+      disposed = true;
+      unlisten?.();
+    };
+  }, [handleReminderAction]);
+
+  useEffect(() => {
+    if (!isTauri()) {
+      return;
+    }
+
+    let disposed = false;
+    let unlisten: (() => void) | null = null;
+
+    void listen<ReminderFiredEvent>('reminder-fired', (event) => {
+      const fired = event.payload;
+      setPendingReminders((current) => [
+        ...current.filter(
+          (reminder) => !(reminder.taskId === fired.taskId && reminder.actionRemindAt === fired.actionRemindAt),
+        ),
+        fired,
+      ]);
+      void initFromBackend().catch((error) => {
+        console.error('Failed to refresh after reminder fired', error);
+      });
+    })
+      .then((stop) => {
+        if (disposed) {
+          stop();
+        } else {
+          unlisten = stop;
+        }
+      })
+      .catch((error) => {
+        console.error('Failed to listen for reminder events', error);
+      });
+
+    return () => {
+      disposed = true;
+      unlisten?.();
+    };
+  }, [initFromBackend]);
+
+  useEffect(() => {
+    if (!isTauri()) {
+      return;
+    }
+
+    let disposed = false;
+    let unlisten: (() => void) | null = null;
+
+    void listen<string>('notification-blocked', () => {
+      setNotificationPermissionGranted(false);
+    })
+      .then((stop) => {
+        if (disposed) {
+          stop();
+        } else {
+          unlisten = stop;
+        }
+      })
+      .catch((error) => {
+        console.error('Failed to listen for notification permission events', error);
+      });
+
+    return () => {
+      disposed = true;
+      unlisten?.();
+    };
+  }, []);
+
   const activeList = lists.find((list) => list.id === activeListId);
   const draftActionPreviews = (draftTask.actions ?? [])
     .map((action, index) => {
@@ -397,6 +537,25 @@ function App() {
     });
   };
 
+  const handleDeleteScheme = async (schemeId: string) => {
+    try {
+      await deleteScheme(schemeId);
+      return true;
+    } catch (error) {
+      // The backend refuses while tasks (including trashed ones) still bind the scheme.
+      const boundTaskCount = schemeBoundTaskCount(error);
+      if (boundTaskCount === null) {
+        throw error;
+      }
+      if (!window.confirm(`有 ${boundTaskCount} 个任务绑定了这个动作，删除后这些绑定会一并移除。确认删除吗？`)) {
+        return false;
+      }
+    }
+
+    await deleteScheme(schemeId, true);
+    return true;
+  };
+
   const handleUpdateList = (list: (typeof lists)[number]) => {
     setEditingList(list);
     setEditListIcon(list.icon);
@@ -424,6 +583,7 @@ function App() {
     void updateList(editingList.id, {
       name: editingList.name,
       icon: editListIcon.trim() || editingList.icon || '🗂️',
+      color: editingList.color ?? null,
     })
       .then(() => {
         setEditingList(null);
@@ -439,14 +599,11 @@ function App() {
   };
 
   const handleExecuteAction = async (task: Task, actionSchemeId: string) => {
-    const action = (task.actions ?? []).find((item) => item.schemeId === actionSchemeId);
+    const actionIndex = (task.actions ?? []).findIndex((item) => item.schemeId === actionSchemeId);
     const scheme = schemes.find((item) => item.id === actionSchemeId);
 
     try {
-      if (!action) {
-        throw new Error('Task action binding not found');
-      }
-      await executeTaskAction(action, scheme);
+      await executeTaskAction(task, actionIndex, scheme);
     } catch (error) {
       console.error('Failed to execute action', error);
       window.alert('动作执行失败，请确认已在 Tauri 桌面端运行，并检查 URL Scheme 是否已安装。');
@@ -518,6 +675,31 @@ function App() {
     return () => window.removeEventListener('keydown', onKeyDown);
   }, [activeView, resetDraftTask, setActiveView]);
 
+  useEffect(() => {
+    if (!isTauri()) {
+      return;
+    }
+
+    const onKeyDown = (event: KeyboardEvent) => {
+      if (event.key.toLowerCase() !== 'z' || !(event.metaKey || event.ctrlKey) || event.shiftKey || event.altKey) {
+        return;
+      }
+      // Leave text undo to inputs that have focus.
+      const target = event.target as HTMLElement | null;
+      if (target && (target.isContentEditable || ['INPUT', 'TEXTAREA', 'SELECT'].includes(target.tagName))) {
+        return;
+      }
+
+      event.preventDefault();
+      void undoLastDelete().catch((error) => {
+        console.error('Failed to undo delete', error);
+      });
+    };
+
+    window.addEventListener('keydown', onKeyDown);
+    return () => window.removeEventListener('keydown', onKeyDown);
+  }, [undoLastDelete]);
+
   if (!isTauri()) {
     return (
       <div className="flex min-h-screen items-center justify-center bg-gray-100 p-6 text-sm text-gray-700">
@@ -557,6 +739,24 @@ function App() {
       });
   };
 
+  const dismissPendingReminder = (reminder: ReminderFiredEvent) => {
+    setPendingReminders((current) => current.filter((item) => item !== reminder));
+  };
+
+  const handlePendingReminderAction = (reminder: ReminderFiredEvent, action: 'complete' | 'snooze') => {
+    dismissPendingReminder(reminder);
+    void handleReminderAction(reminder.taskId, reminder.actionRemindAt, action).catch((error) => {
+      console.error('Failed to handle reminder action', error);
+      window.alert('提醒操作失败，请稍后重试。');
+    });
+  };
+
+  // Cards for tasks that were completed or deleted elsewhere are no longer actionable.
+  const visiblePendingReminders = pendingReminders.flatMap((reminder) => {
+    const task = tasks.find((item) => item.id === reminder.taskId);
+    return task && !task.completed ? [{ reminder, task }] : [];
+  });
+
   const handleClearReminderQueue = () => {
     if (!window.confirm('确认清空 SQLite 任务队列（已触发提醒记录）吗？')) {
       return;
@@ -727,10 +927,51 @@ function App() {
         onClose={() => setIsSettingsOpen(false)}
         onCreate={addScheme}
         onUpdate={updateScheme}
-        onDelete={deleteScheme}
+        onDelete={handleDeleteScheme}
         onExportBackup={exportBackup}
         onImportBackup={importBackup}
       />
+      {visiblePendingReminders.length > 0 ? (
+        <div className="fixed bottom-4 right-4 z-40 flex w-80 flex-col gap-2">
+          {visiblePendingReminders.map(({ reminder, task }) => (
+            <div
+              key={`${reminder.taskId}-${reminder.actionRemindAt}`}
+              className="rounded-xl border border-gray-200 bg-white p-4 shadow-lg"
+            >
+              <div className="mb-3 flex items-start justify-between gap-2">
+                <div className="min-w-0">
+                  <p className="text-xs text-gray-400">任务提醒</p>
+                  <p className="truncate text-sm font-medium text-gray-800">{task.title}</p>
+                </div>
+                <button
+                  type="button"
+                  onClick={() => dismissPendingReminder(reminder)}
+                  className="rounded px-1 text-gray-400 transition hover:bg-gray-100 hover:text-gray-600"
+                  aria-label="关闭提醒"
+                >
+                  ×
+                </button>
+              </div>
+              <div className="flex justify-end gap-2">
+                <button
+                  type="button"
+                  onClick={() => handlePendingReminderAction(reminder, 'snooze')}
+                  className="rounded-lg px-3 py-1.5 text-sm text-gray-500 transition hover:bg-gray-100"
+                >
+                  稍后提醒（10 分钟）
+                </button>
+                <button
+                  type="button"
+                  onClick={() => handlePendingReminderAction(reminder, 'complete')}
+                  className="rounded-lg bg-linkflow-accent px-3 py-1.5 text-sm text-white transition hover:opacity-90"
+                >
+                  完成
+                </button>
+              </div>
+            </div>
+          ))}
+        </div>
+      ) : null}
       {isCreateListOpen ? (
         <div className="fixed inset-0 z-50 flex items-center justify-center bg-black/30 p-4">
           <div className="w-full max-w-md rounded-xl bg-white p-5 shadow-lg">
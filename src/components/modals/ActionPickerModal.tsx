@@ -75,7 +75,7 @@ export function ActionPickerModal({
     const keyword = query.trim().toLowerCase();
 
     return schemes.filter((scheme) => {
-      if (selectedSchemeIds.has(scheme.id)) {
+      if (scheme.enabled === false || selectedSchemeIds.has(scheme.id)) {
         return false;
       }
       if (!keyword) {
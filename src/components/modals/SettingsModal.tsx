@@ -22,7 +22,8 @@ interface SettingsModalProps {
   onClose: () => void;
   onCreate: (input: SchemeDraft) => Promise<void>;
   onUpdate: (schemeId: string, patch: SchemeDraft) => Promise<void>;
-  onDelete: (schemeId: string) => Promise<void>;
+  /** Resolves to `false` when the user backs out of the deletion. */
+  onDelete: (schemeId: string) => Promise<boolean>;
   onExportBackup: (path: string) => Promise<string>;
   onImportBackup: (path: string) => Promise<void>;
 }
@@ -83,7 +84,9 @@ export function SettingsModal({
     }
     setIsSubmitting(true);
     try {
-      await onDelete(selectedId);
+      if (!(await onDelete(selectedId))) {
+        return;
+      }
       setSelectedId(null);
       setDraft(emptyDraft);
       setSavedNotice('已删除动作');
@@ -250,7 +253,11 @@ export function SettingsModal({
                     kind: value as SchemeDraft['kind'],
                   }))
                 }
-                options={[{ value: 'url', label: 'URL Scheme' }]}
+                options={[
+                  { value: 'url', label: 'URL Scheme' },
+                  { value: 'clipboard', label: '复制到剪贴板' },
+                  { value: 'shell', label: '运行命令（需在设置中开启）' },
+                ]}
                 className="w-full"
               />
             </label>
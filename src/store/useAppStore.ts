@@ -11,11 +11,14 @@ import {
   deleteTask as deleteTaskInDb,
   exportBackup as exportBackupInDb,
   getAppSnapshot,
+  handleReminderAction as handleReminderActionInDb,
   importBackup as importBackupInDb,
   saveTask as saveTaskInDb,
   toggleTaskCompleted as toggleTaskCompletedInDb,
+  undoLastDelete as undoLastDeleteInDb,
   updateList as updateListInDb,
   updateScheme as updateSchemeInDb,
+  type ReminderAction,
 } from '../utils/backendApi';
 import { applyScheduleInvariants, reduceSchedule, toRelativeReminder, type ScheduleAction } from '../utils/schedule';
 
@@ -46,17 +49,19 @@ interface AppState {
   setActiveList: (listId: string) => void;
   addList: (input: Omit<List, 'id'>) => Promise<void>;
   updateList: (listId: string, patch: Omit<List, 'id'>) => Promise<void>;
-  deleteList: (listId: string) => Promise<void>;
+  deleteList: (listId: string, reassignTo?: string) => Promise<void>;
   setActiveView: (view: ActiveView) => void;
   toggleTaskCompleted: (taskId: string) => Promise<void>;
+  handleReminderAction: (taskId: string, remindAt: number, action: ReminderAction) => Promise<void>;
   deleteTask: (taskId: string) => Promise<void>;
+  undoLastDelete: () => Promise<boolean>;
   clearCompletedTasks: () => Promise<number>;
   clearReminderQueue: () => Promise<number>;
   updateTask: (taskId: string, patch: Partial<Task>) => Promise<void>;
   addTaskFromDraft: (defaultListId: string, useDraftList: boolean) => Promise<void>;
   addScheme: (input: Omit<UrlScheme, 'id'>) => Promise<void>;
   updateScheme: (schemeId: string, patch: Omit<UrlScheme, 'id'>) => Promise<void>;
-  deleteScheme: (schemeId: string) => Promise<void>;
+  deleteScheme: (schemeId: string, force?: boolean) => Promise<void>;
   exportBackup: (path: string) => Promise<string>;
   importBackup: (path: string) => Promise<void>;
   updateDraftTask: (patch: Partial<DraftTask>) => void;
@@ -286,8 +291,8 @@ export const useAppStore = create<AppState>((set, get) => ({
       lists: state.lists.map((list) => (list.id === updated.id ? updated : list)),
     }));
   },
-  deleteList: async (listId) => {
-    await deleteListInDb(listId);
+  deleteList: async (listId, reassignTo) => {
+    await deleteListInDb(listId, reassignTo);
     set((state) => {
       const nextLists = state.lists.filter((list) => list.id !== listId);
       const nextActiveListId =
@@ -299,7 +304,7 @@ export const useAppStore = create<AppState>((set, get) => ({
           task.listId === listId
             ? {
                 ...task,
-                listId: undefined,
+                listId: reassignTo,
               }
             : task,
         ),
@@ -321,17 +326,34 @@ export const useAppStore = create<AppState>((set, get) => ({
   },
   setActiveView: (view) => set({ activeView: view }),
   toggleTaskCompleted: async (taskId) => {
-    const updated = await toggleTaskCompletedInDb(taskId);
+    const { task: updated, spawned } = await toggleTaskCompletedInDb(taskId);
     set((state) => ({
-      tasks: state.tasks.map((task) => (task.id === updated.id ? updated : task)),
+      tasks: [
+        ...state.tasks.map((task) => (task.id === updated.id ? updated : task)),
+        ...(spawned ? [spawned] : []),
+      ],
     }));
   },
+  handleReminderAction: async (taskId, remindAt, action) => {
+    await handleReminderActionInDb(taskId, remindAt, action);
+    // Completing may spawn the next repeat, so reload rather than patching one task.
+    await get().initFromBackend();
+  },
   deleteTask: async (taskId) => {
     await deleteTaskInDb(taskId);
     set((state) => ({
       tasks: state.tasks.filter((task) => task.id !== taskId),
     }));
   },
+  undoLastDelete: async () => {
+    const restored = await undoLastDeleteInDb();
+    if (!restored) {
+      return false;
+    }
+    // A restored list takes its tasks back, so reload rather than patching.
+    await get().initFromBackend();
+    return true;
+  },
   clearCompletedTasks: async () => {
     const deleted = await clearCompletedTasksInDb();
     set((state) => ({
@@ -419,8 +441,8 @@ export const useAppStore = create<AppState>((set, get) => ({
       schemes: state.schemes.map((scheme) => (scheme.id === updated.id ? updated : scheme)),
     }));
   },
-  deleteScheme: async (schemeId) => {
-    await deleteSchemeInDb(schemeId);
+  deleteScheme: async (schemeId, force) => {
+    await deleteSchemeInDb(schemeId, force);
     set((state) => ({
       schemes: state.schemes.filter((scheme) => scheme.id !== schemeId),
       tasks: state.tasks.map((task) => ({
@@ -435,7 +457,7 @@ export const useAppStore = create<AppState>((set, get) => ({
   },
   exportBackup: async (path) => exportBackupInDb(path),
   importBackup: async (path) => {
-    const snapshot = await importBackupInDb(path);
+    const { snapshot } = await importBackupInDb(path);
     set((state) => ({
       lists: snapshot.lists,
         tasks: snapshot.tasks.map((task) => ({
@@ -1,10 +1,13 @@
+export type SchemeKind = 'url' | 'clipboard' | 'shell';
+
 export interface UrlScheme {
   id: string;
   name: string;
   icon: string;
   template: string;
-  kind?: 'url';
+  kind?: SchemeKind;
   paramType: 'string' | 'number';
+  enabled?: boolean;
 }
 
 export type RepeatType = 'daily' | 'weekly' | 'monthly';
@@ -13,6 +16,13 @@ export interface RepeatRule {
   type: RepeatType;
   dayOfWeek?: number[];
   dayOfMonth?: number[];
+  weekOfMonth?: number | null;
+  interval?: number;
+  until?: string;
+  count?: number;
+  onShortMonth?: 'skip' | 'last_day';
+  /** Daily only: Saturday and Sunday occurrences move to Monday. */
+  skipWeekends?: boolean;
 }
 
 export interface RelativeReminder {
@@ -20,7 +30,20 @@ export interface RelativeReminder {
   offsetMinutes: number;
 }
 
-export type TaskReminder = RelativeReminder | null;
+export interface AfterCompletionReminder {
+  type: 'after_completion_of';
+  offsetMinutes: number;
+  dependsOnTaskId: string;
+}
+
+export interface AbsoluteReminder {
+  type: 'absolute';
+  at: string;
+}
+
+export type TaskReminder = RelativeReminder | AfterCompletionReminder | AbsoluteReminder | null;
+
+export type TaskPriority = 'low' | 'normal' | 'high';
 
 export interface Task {
   id: string;
@@ -28,11 +51,19 @@ export interface Task {
   title: string;
   detail?: string;
   completed: boolean;
+  completedAt?: number | null;
   dueDate?: string | null;
   time?: string | null;
   reminder?: TaskReminder;
   repeat?: RepeatRule | null;
   actions?: TaskActionBinding[];
+  priority?: TaskPriority;
+  tags?: string[];
+  timezone?: string | null;
+  color?: string | null;
+  /** RFC 3339 UTC timestamps maintained by the backend. */
+  createdAt?: string | null;
+  updatedAt?: string | null;
 }
 
 export interface TaskActionBinding {
@@ -44,4 +75,13 @@ export interface List {
   id: string;
   name: string;
   icon: string;
+  color?: string | null;
+  defaultReminderOffset?: number | null;
+}
+
+export interface ReminderFiredEvent {
+  taskId: string;
+  remindAt: number;
+  /** The instant to pass to `handleReminderAction`; the original due time for a snoozed reminder. */
+  actionRemindAt: number;
 }
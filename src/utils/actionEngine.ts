@@ -1,18 +1,37 @@
 import { isTauri } from '@tauri-apps/api/core';
 import { open } from '@tauri-apps/plugin-shell';
-import type { TaskActionBinding, UrlScheme } from '../types/models';
+import type { Task, UrlScheme } from '../types/models';
+import { runShellAction } from './backendApi';
 
 export function buildActionUrl(template: string, params: string[] = []): string {
   return params.reduce((url, param) => url.replace('{param}', param), template);
 }
 
-export async function executeTaskAction(binding: TaskActionBinding, scheme?: UrlScheme): Promise<void> {
+export async function executeTaskAction(task: Task, actionIndex: number, scheme?: UrlScheme): Promise<void> {
+  const binding = task.actions?.[actionIndex];
+  if (!binding) {
+    throw new Error('Task action binding not found');
+  }
   if (!scheme) {
     throw new Error('Action scheme not found');
   }
 
+  if (scheme.kind === 'shell') {
+    // Resolved and quoted by the backend, which also enforces the opt-in setting.
+    const output = await runShellAction(task.id, actionIndex);
+    if (output.exitCode !== 0) {
+      throw new Error(output.stderr || `Command exited with code ${output.exitCode ?? 'unknown'}`);
+    }
+    return;
+  }
+
   const finalUrl = buildActionUrl(scheme.template, binding.params ?? []);
 
+  if (scheme.kind === 'clipboard') {
+    await navigator.clipboard.writeText(finalUrl);
+    return;
+  }
+
   if (!finalUrl) {
     throw new Error('Action URL is empty');
   }
@@ -18,6 +18,8 @@ export interface NewTaskInput {
   reminder?: Task['reminder'];
   repeat?: Task['repeat'];
   actions?: Task['actions'];
+  color?: string | null;
+  skipDefaultReminder?: boolean;
 }
 
 function ensureTauri(): void {
@@ -31,16 +33,44 @@ export async function getAppSnapshot(): Promise<AppSnapshot> {
   return invoke<AppSnapshot>('get_app_snapshot');
 }
 
-export async function exportBackup(path: string): Promise<string> {
+export async function getCachedSnapshot(): Promise<AppSnapshot | null> {
   ensureTauri();
-  return invoke<string>('export_backup', { path });
+  return invoke<AppSnapshot | null>('get_cached_snapshot');
 }
 
-export async function importBackup(path: string): Promise<AppSnapshot> {
+export async function exportBackup(path: string, passphrase?: string): Promise<string> {
   ensureTauri();
-  return invoke<AppSnapshot>('import_backup', { path });
+  return invoke<string>('export_backup', { path, passphrase });
 }
 
+export type ImportMode = 'replace' | 'merge';
+
+export interface MergeCounts {
+  added: number;
+  updated: number;
+}
+
+export interface BackupImportResult {
+  snapshot: AppSnapshot;
+  lists: MergeCounts;
+  schemes: MergeCounts;
+  tasks: MergeCounts;
+}
+
+export async function importBackup(
+  path: string,
+  importMode: ImportMode = 'replace',
+  passphrase?: string,
+): Promise<BackupImportResult> {
+  ensureTauri();
+  return invoke<BackupImportResult>('import_backup', { path, importMode, passphrase });
+}
+
+
+export async function getList(listId: string): Promise<List> {
+  ensureTauri();
+  return invoke<List>('get_list', { listId });
+}
 
 export async function createList(input: Omit<List, 'id'>): Promise<List> {
   ensureTauri();
@@ -52,6 +82,11 @@ export async function updateList(listId: string, patch: Omit<List, 'id'>): Promi
   return invoke<List>('update_list', { listId, patch });
 }
 
+export async function getScheme(schemeId: string): Promise<UrlScheme> {
+  ensureTauri();
+  return invoke<UrlScheme>('get_scheme', { schemeId });
+}
+
 export async function createScheme(input: SchemeDraftInput): Promise<UrlScheme> {
   ensureTauri();
   return invoke<UrlScheme>('create_scheme', { input });
@@ -62,9 +97,28 @@ export async function updateScheme(schemeId: string, patch: SchemeDraftInput): P
   return invoke<UrlScheme>('update_scheme', { schemeId, patch });
 }
 
-export async function deleteScheme(schemeId: string): Promise<void> {
+/** Rejects while tasks still bind the scheme unless `force` is set; resolves to the number of tasks unbound. */
+export async function deleteScheme(schemeId: string, force = false): Promise<number> {
   ensureTauri();
-  await invoke('delete_scheme', { schemeId });
+  return invoke<number>('delete_scheme', { schemeId, force });
+}
+
+/** The bound-task count from a `deleteScheme` rejection made without `force`, or null for any other error. */
+export function schemeBoundTaskCount(error: unknown): number | null {
+  const message = error instanceof Error ? error.message : String(error);
+  const match = /Scheme is bound to (\d+) task/.exec(message);
+  return match ? Number(match[1]) : null;
+}
+
+export interface ShellActionOutput {
+  exitCode: number | null;
+  stdout: string;
+  stderr: string;
+}
+
+export async function runShellAction(taskId: string, actionIndex: number): Promise<ShellActionOutput> {
+  ensureTauri();
+  return invoke<ShellActionOutput>('run_shell_action', { taskId, actionIndex });
 }
 
 export async function createTask(input: NewTaskInput): Promise<Task> {
@@ -77,9 +131,44 @@ export async function saveTask(task: Task): Promise<Task> {
   return invoke<Task>('save_task', { task });
 }
 
-export async function toggleTaskCompleted(taskId: string): Promise<Task> {
+export interface ToggleTaskResult {
+  task: Task;
+  /** The next occurrence, when completing a repeating task created one. */
+  spawned: Task | null;
+}
+
+export async function toggleTaskCompleted(taskId: string): Promise<ToggleTaskResult> {
   ensureTauri();
-  return invoke<Task>('toggle_task_completed', { taskId });
+  return invoke<ToggleTaskResult>('toggle_task_completed', { taskId });
+}
+
+export type ReminderAction = 'complete' | 'snooze';
+
+export async function handleReminderAction(
+  taskId: string,
+  remindAt: number,
+  action: ReminderAction,
+): Promise<void> {
+  ensureTauri();
+  await invoke('handle_reminder_action', { taskId, remindAt, action });
+}
+
+export type NotificationPermission = 'granted' | 'denied' | 'default';
+
+export async function notificationPermissionStatus(): Promise<NotificationPermission> {
+  ensureTauri();
+  return invoke<NotificationPermission>('notification_permission_status');
+}
+
+export async function requestNotificationPermission(): Promise<NotificationPermission> {
+  ensureTauri();
+  return invoke<NotificationPermission>('request_notification_permission');
+}
+
+/** Shows the task's reminder notification right away (or a sample without a task) without marking it fired. */
+export async function testNotification(taskId?: string): Promise<void> {
+  ensureTauri();
+  await invoke('test_notification', { taskId });
 }
 
 export async function deleteTask(taskId: string): Promise<void> {
@@ -97,7 +186,28 @@ export async function clearReminderQueue(): Promise<number> {
   return invoke<number>('clear_reminder_queue');
 }
 
-export async function deleteList(listId: string): Promise<void> {
+/** Lets the task's reminders fire again; resolves to the number of fired records removed. */
+export async function resetFiredReminders(taskId: string): Promise<number> {
+  ensureTauri();
+  return invoke<number>('reset_fired_reminders', { taskId });
+}
+
+export interface DeletedEntity {
+  kind: 'task' | 'list';
+  task?: Task;
+  list?: List;
+  taskIds: string[];
+  movedTo?: string | null;
+}
+
+/** Restores the most recent task or list deletion from the last few minutes; resolves to null when there is none. */
+export async function undoLastDelete(): Promise<DeletedEntity | null> {
+  ensureTauri();
+  return invoke<DeletedEntity | null>('undo_last_delete');
+}
+
+/** Moves the list's tasks to `reassignTo` when given, otherwise leaves them unfiled; resolves to the number moved. */
+export async function deleteList(listId: string, reassignTo?: string): Promise<number> {
   ensureTauri();
-  await invoke('delete_list', { listId });
+  return invoke<number>('delete_list', { listId, reassignTo });
 }